@@ -1,135 +1,593 @@
+use crate::app::columns::{Column, SORT_COLUMNS};
 use crate::app::confirmation::ConfirmAction;
-use crate::data::{Data, fetch_data};
+use crate::app::filter::{Filter, FilterInput};
+use crate::app::log_buffer::{LogBuffer, LogPos};
+use crate::app::log_search::{LogSearch, SearchInput};
+use crate::app::notifications::{Level, Notifications};
+use crate::app::prompt::Prompt;
+use crate::app::queue::QueuedAction;
+use crate::app::severity::Classifier;
+use crate::config::Config;
+use crate::data::{
+    Data, JobData, JobSummary, Update, apply_update, summarize_jobs, watch_data, watch_jobs,
+};
+use crate::k8s;
 
+pub mod ansi;
+pub mod columns;
+pub mod command;
 pub mod confirmation;
+pub mod containers;
+pub mod debug;
+pub mod export;
+pub mod filter;
+pub mod frames;
+pub mod jobs;
+pub mod log_buffer;
+pub mod log_search;
 pub mod logs;
+pub mod notifications;
+pub mod prompt;
+pub mod queue;
+pub mod scope;
+pub mod selection;
+pub mod severity;
 pub mod table;
 
+use color_eyre::Result;
+use crossterm::event::EventStream;
+use futures::StreamExt;
 use k8s_openapi::chrono::{DateTime, Utc};
 use kube::Client;
 use ratatui::{
     DefaultTerminal, Frame,
-    crossterm::event::{self, Event, KeyCode, KeyEventKind},
-    widgets::TableState,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Rect},
+    widgets::{ListState, TableState},
 };
-use std::error::Error;
-use std::time::Duration;
-use tokio::runtime::Runtime;
+use std::collections::BTreeSet;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
 
 pub struct App {
     state: TableState,
     items: Vec<Data>,
-    rt: Runtime,
+    /// Indices into `items` of the pods the pod table shows, in the order they are shown. See
+    /// [`App::update_visible_pods`].
+    visible: Vec<usize>,
+    /// Pods marked in the pod table, by name, for bulk actions
+    marked: BTreeSet<String>,
+    /// Pod a range selection started from, see [`App::visual_key`]
+    visual_anchor: Option<String>,
+    job_state: TableState,
+    jobs: Vec<JobData>,
+    /// Rows of the jobs table for everyone's jobs, updated as pods and Jobs change
+    summaries: Vec<JobSummary>,
+    view: View,
+    /// Job whose pods the pod table is narrowed to, after drilling down from the jobs table, or
+    /// whose frames the frame grid shows
+    job_filter: Option<String>,
+    /// Narrows the pod table, see [`Filter`]
+    filter: Filter,
+    filter_input: Option<FilterInput>,
+    /// Column the pod table is sorted by, one of [`SORT_COLUMNS`]
+    sort: Column,
+    /// Sort the pod table the other way around
+    sort_reversed: bool,
+    /// Artist running mf, see [`scope::current_artist`]
+    artist: Option<String>,
+    /// Show only the artist's own pods and jobs
+    mine: bool,
+    /// The artist was guessed rather than configured, and the first pod listing hasn't been
+    /// checked for any of their pods yet
+    artist_guessed: bool,
+    /// Completion index under the cursor in the frame grid
+    frame_cursor: usize,
+    /// Cells per row in the frame grid as last drawn, for moving up and down
+    frame_columns: usize,
     mode: Mode,
-    scroll_offset: u16,
-    max_log_lines: u16,
-    logs: Vec<String>,
-    log_rx: Option<tokio::sync::mpsc::UnboundedReceiver<String>>,
+    /// Most recent lines of the open log stream
+    logs: LogBuffer,
+    /// Tells errors, warnings and info in log lines apart
+    classifier: Classifier,
+    /// Top of the log view while scrolled back, following new lines when unset
+    log_top: Option<LogPos>,
+    /// Where the log lines were last drawn, for scrolling
+    log_viewport: Rect,
+    /// Lines up to here were on screen while following, the rest are new since scrolling back
+    log_seen: u64,
+    /// Wrap long log lines rather than scroll sideways
+    log_wrap: bool,
+    /// Columns scrolled sideways while not wrapping
+    log_hscroll: u16,
+    /// Show line numbers next to the logs
+    log_gutter: bool,
+    log_search: Option<LogSearch>,
+    log_search_input: Option<SearchInput>,
+    /// Container whose logs are shown, the API server's default when unset
+    log_container: Option<String>,
+    /// Show the logs of the container's previous run instead of following the current one
+    log_previous: bool,
+    /// Latest `ALF_PROGRESS` the open logs printed, for the ETA gauge
+    log_progress: Option<u16>,
+    /// DCC the open pod runs, going by `dcc_label`, whose patterns tell the severity of its lines
+    log_dcc: Option<String>,
+    /// Selection in the container picker while it's open
+    container_picker: Option<ListState>,
+    log_rx: Option<UnboundedReceiver<String>>,
     log_task: Option<tokio::task::JoinHandle<()>>,
+    tx: UnboundedSender<Message>,
+    rx: UnboundedReceiver<Message>,
+    host_schedulable: Option<Result<bool, String>>,
     client: Client,
+    config: Config,
     confirmation_popup: bool,
     pending_confirmation: Option<ConfirmAction>,
+    /// Confirmed cancellations waiting out their grace period, oldest first
+    queued: Vec<QueuedAction>,
+    notifications: Notifications,
+    messages_state: ListState,
+    command: Option<String>,
+    prompt: Option<Prompt>,
+    show_debug: bool,
+}
+
+/// Which table is shown in [`Mode::Table`]
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum View {
+    Pods,
+    Jobs,
+    /// Completion indexes of one Indexed Job
+    Frames,
 }
 
 enum Mode {
     Table,
     Logs { pod: String, start: DateTime<Utc> },
+    Messages,
+}
+
+/// Results posted back to the UI by background tasks
+pub enum Message {
+    Pods(Update<Data>),
+    Jobs(Update<JobData>),
+    HostSchedulable(Result<bool, String>),
+    Checkout {
+        schedulable: bool,
+        result: Result<(), String>,
+    },
+    /// Outcome of deleting each resolved Job
+    CancelJobs {
+        controllers: Vec<String>,
+        dry_run: bool,
+        results: Vec<(String, Result<(), String>)>,
+    },
+    SuspendJobs {
+        controller: String,
+        suspend: bool,
+        result: Result<usize, String>,
+    },
+    Parallelism {
+        controller: String,
+        parallelism: i32,
+        result: Result<usize, String>,
+    },
+    RetryFrames {
+        job: String,
+        /// Retry Jobs created, also when creating a later one failed
+        created: Vec<String>,
+        result: Result<(), String>,
+    },
+    DeletePod {
+        pod: String,
+        result: Result<(), String>,
+    },
+    AvoidNode {
+        node: String,
+        artist: String,
+        result: Result<(), String>,
+    },
+}
+
+impl From<Update<Data>> for Message {
+    fn from(update: Update<Data>) -> Self {
+        Message::Pods(update)
+    }
+}
+
+impl From<Update<JobData>> for Message {
+    fn from(update: Update<JobData>) -> Self {
+        Message::Jobs(update)
+    }
 }
 
 impl App {
-    pub fn new() -> Result<Self, Box<dyn Error>> {
-        let rt = Runtime::new()?;
-        let client = rt.block_on(Client::try_default())?;
-        let items = rt.block_on(fetch_data(client.clone()))?;
+    pub async fn new(config: Config) -> Result<Self> {
+        let client = k8s::client(&config).await?;
+        let (tx, rx) = unbounded_channel();
+        let artist = scope::current_artist(&config);
+        tokio::spawn(watch_data(client.clone(), config.clone(), tx.clone()));
+        tokio::spawn(watch_jobs(client.clone(), config.clone(), tx.clone()));
+        tokio::spawn(table::poll_host(
+            client.clone(),
+            config.checkout_label.clone(),
+            tx.clone(),
+        ));
         Ok(Self {
             state: TableState::default().with_selected(0),
-            items,
-            rt,
+            items: Vec::new(),
+            visible: Vec::new(),
+            marked: BTreeSet::new(),
+            visual_anchor: None,
+            job_state: TableState::default().with_selected(0),
+            jobs: Vec::new(),
+            summaries: Vec::new(),
+            view: View::Pods,
+            job_filter: None,
+            filter: Filter::default(),
+            filter_input: None,
+            sort: SORT_COLUMNS[0].clone(),
+            sort_reversed: false,
+            mine: artist.is_some(),
+            artist_guessed: config.artist.is_none() && artist.is_some(),
+            artist,
+            frame_cursor: 0,
+            frame_columns: 1,
             mode: Mode::Table,
-            scroll_offset: 0,
-            max_log_lines: 0,
-            logs: Vec::new(),
+            logs: LogBuffer::new(config.log_buffer_lines),
+            classifier: Classifier::new(&config.log_patterns)?,
+            log_top: None,
+            log_viewport: Rect::default(),
+            log_seen: 0,
+            log_wrap: true,
+            log_hscroll: 0,
+            log_gutter: true,
+            log_search: None,
+            log_search_input: None,
+            log_container: None,
+            log_previous: false,
+            log_progress: None,
+            log_dcc: None,
+            container_picker: None,
             log_rx: None,
             log_task: None,
+            tx,
+            rx,
+            host_schedulable: None,
             client,
+            config,
             confirmation_popup: false,
             pending_confirmation: None,
+            queued: Vec::new(),
+            notifications: Notifications::default(),
+            messages_state: ListState::default(),
+            command: None,
+            prompt: None,
+            show_debug: false,
         })
     }
 
-    /// Main app loop
-    pub fn run(mut self, mut terminal: DefaultTerminal) -> Result<(), Box<dyn Error>> {
-        let tick = Duration::from_millis(500);
+    /// Main app loop. Waits on terminal input, background task results and a render tick, so
+    /// nothing here blocks on the cluster.
+    pub async fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
+        let mut events = EventStream::new();
+        let mut tick = tokio::time::interval(self.config.refresh_interval);
         loop {
             terminal.draw(|frame| self.draw(frame))?;
-            if event::poll(tick)? {
-                if let Event::Key(key) = event::read()? {
-                    if key.kind == KeyEventKind::Press {
-                        if self.handle_key(key)? {
-                            return Ok(());
-                        }
-                        if matches!(self.mode, Mode::Logs { .. }) {
-                            self.drain_logs();
-                        }
+            tokio::select! {
+                _ = tick.tick() => {
+                    self.notifications.expire();
+                    self.run_due_actions();
+                }
+                Some(event) = events.next() => {
+                    if let Event::Key(key) = event?
+                        && key.kind == KeyEventKind::Press
+                        && self.handle_key(key)?
+                    {
+                        return Ok(());
                     }
                 }
-            } else {
-                if matches!(self.mode, Mode::Logs { .. }) {
+                Some(message) = self.rx.recv() => {
+                    self.handle_message(message);
+                    while let Ok(message) = self.rx.try_recv() {
+                        self.handle_message(message);
+                    }
+                }
+                Some(line) = next_log_line(&mut self.log_rx) => {
+                    self.push_log(line);
                     self.drain_logs();
-                } else {
-                    if let Ok(items) = self.rt.block_on(fetch_data(self.client.clone())) {
-                        self.items = items;
+                }
+            }
+        }
+    }
+
+    /// Apply a background task result to the app state
+    fn handle_message(&mut self, message: Message) {
+        match message {
+            Message::Pods(Update::Failed(e)) => {
+                self.notify(Level::Error, format!("Can't watch pods, retrying: {e}"))
+            }
+            Message::Pods(update) => {
+                let listed = matches!(update, Update::Reset(_));
+                self.keep_selected_pod(|app| apply_update(&mut app.items, update));
+                self.summaries = summarize_jobs(&self.items, &self.jobs);
+                if listed {
+                    self.check_guessed_artist();
+                }
+            }
+            Message::Jobs(Update::Failed(e)) => {
+                self.notify(Level::Error, format!("Can't watch jobs, retrying: {e}"))
+            }
+            Message::Jobs(update) => {
+                apply_update(&mut self.jobs, update);
+                self.summaries = summarize_jobs(&self.items, &self.jobs);
+            }
+            Message::HostSchedulable(result) => self.host_schedulable = Some(result),
+            Message::Checkout {
+                schedulable,
+                result,
+            } => match result {
+                Ok(()) if schedulable => {
+                    self.notify(Level::Success, "Your node is back on the farm")
+                }
+                Ok(()) => self.notify(Level::Success, "Your node is checked out of the farm"),
+                Err(e) => self.notify(
+                    Level::Error,
+                    format!("Failed to mark host schedulable: {e}"),
+                ),
+            },
+            Message::CancelJobs {
+                controllers,
+                dry_run,
+                results,
+            } => {
+                let controller = controllers.join(", ");
+                let cancelled = results.iter().filter(|(_, r)| r.is_ok()).count();
+                for (job, result) in results {
+                    if let Err(e) = result {
+                        let text = if dry_run {
+                            format!("Dry run rejected for job {job}: {e}")
+                        } else {
+                            format!("Failed to cancel job {job}: {e}")
+                        };
+                        self.notify(Level::Error, text);
                     }
                 }
+                match (dry_run, cancelled) {
+                    (true, _) => self.notify(
+                        Level::Info,
+                        format!("Dry run: {cancelled} job(s) of {controller} would be cancelled"),
+                    ),
+                    (false, 0) => {}
+                    (false, _) => self.notify(
+                        Level::Success,
+                        format!("Cancelled {cancelled} job(s) of {controller}"),
+                    ),
+                }
             }
+            Message::SuspendJobs {
+                controller,
+                suspend,
+                result,
+            } => {
+                let (verb, done) = if suspend {
+                    ("suspend", "Suspended")
+                } else {
+                    ("resume", "Resumed")
+                };
+                match result {
+                    Ok(0) => self.notify(Level::Error, format!("No jobs found for {controller}")),
+                    Ok(count) => self.notify(
+                        Level::Success,
+                        format!("{done} {count} job(s) of {controller}"),
+                    ),
+                    Err(e) => self.notify(
+                        Level::Error,
+                        format!("Failed to {verb} jobs for {controller}: {e}"),
+                    ),
+                }
+            }
+            Message::Parallelism {
+                controller,
+                parallelism,
+                result,
+            } => match result {
+                Ok(0) => self.notify(Level::Error, format!("No jobs found for {controller}")),
+                Ok(count) => self.notify(
+                    Level::Success,
+                    format!("Set parallelism of {count} job(s) of {controller} to {parallelism}"),
+                ),
+                Err(e) => self.notify(
+                    Level::Error,
+                    format!("Failed to set parallelism of {controller}: {e}"),
+                ),
+            },
+            Message::RetryFrames {
+                job,
+                created,
+                result,
+            } => match result {
+                Ok(()) => self.notify(
+                    Level::Success,
+                    format!("Retrying {} frame(s) of {job}", created.len()),
+                ),
+                Err(e) if created.is_empty() => self.notify(
+                    Level::Error,
+                    format!("Failed to retry frames of {job}: {e}"),
+                ),
+                Err(e) => self.notify(
+                    Level::Error,
+                    format!(
+                        "Retrying only {} frame(s) of {job} ({}), the rest failed: {e}",
+                        created.len(),
+                        created.join(", ")
+                    ),
+                ),
+            },
+            Message::DeletePod { pod, result } => match result {
+                Ok(()) => self.notify(Level::Success, format!("Deleted pod {pod}")),
+                Err(e) => self.notify(Level::Error, format!("Failed to delete pod {pod}: {e}")),
+            },
+            Message::AvoidNode {
+                node,
+                artist,
+                result,
+            } => match result {
+                Ok(()) => self.notify(Level::Success, format!("{artist}'s pods will avoid {node}")),
+                Err(e) => self.notify(
+                    Level::Error,
+                    format!("Failed to mark {node} to be avoided for {artist}: {e}"),
+                ),
+            },
         }
     }
 
     pub fn draw(&mut self, frame: &mut Frame) {
+        let banner_height = u16::from(!self.queued.is_empty());
+        let [main, banner, status] = Layout::vertical([
+            Constraint::Min(1),
+            Constraint::Length(banner_height),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
         match &self.mode {
-            Mode::Table => self.draw_table(frame),
+            Mode::Table => self.draw_table(frame, main),
             Mode::Logs { pod, start } => {
                 let pod = pod.clone();
-                self.draw_logs(frame, &pod, &start.clone());
+                self.draw_logs(frame, main, &pod, &start.clone());
             }
+            Mode::Messages => self.draw_messages(frame, main),
         }
+        self.draw_queue_banner(frame, banner);
+        self.draw_status_line(frame, status);
+        self.draw_toasts(frame);
+        self.draw_debug(frame);
     }
 
     /// Keybinds
-    fn handle_key(&mut self, key: event::KeyEvent) -> Result<bool, Box<dyn Error>> {
+    fn handle_key(&mut self, key: event::KeyEvent) -> Result<bool> {
+        if self.command.is_some() {
+            return Ok(self.handle_command_key(key));
+        }
+        if self.prompt.is_some() {
+            self.handle_prompt_key(key);
+            return Ok(false);
+        }
+        if self.filter_input.is_some() {
+            self.handle_filter_key(key);
+            return Ok(false);
+        }
+        if self.container_picker.is_some() {
+            self.handle_container_key(key);
+            return Ok(false);
+        }
+        if self.log_search_input.is_some() {
+            self.handle_search_key(key);
+            return Ok(false);
+        }
+        if key.code == KeyCode::F(12) {
+            self.show_debug = !self.show_debug;
+            return Ok(false);
+        }
+        if key.code == KeyCode::Char('u') && key.modifiers.is_empty() && !self.queued.is_empty() {
+            self.undo_key();
+            return Ok(false);
+        }
         match &self.mode {
             // Keybinds while in default pod table
             Mode::Table => match key.code {
+                KeyCode::Enter if self.view == View::Jobs => self.open_job(),
+                KeyCode::Enter if self.view == View::Frames => self.open_frame_logs(),
                 KeyCode::Enter => self.start_log_mode(),
-                KeyCode::Char('q') | KeyCode::Esc => return Ok(true),
+                KeyCode::Esc if self.confirmation_popup => self.no_key(),
+                KeyCode::Esc if self.view == View::Pods && self.has_selection() => {
+                    self.clear_marks()
+                }
+                KeyCode::Esc if !self.filter.is_empty() && self.view == View::Pods => {
+                    self.clear_filter()
+                }
+                KeyCode::Esc if self.job_filter.is_some() => self.close_job(),
+                KeyCode::Char('f') if self.view == View::Jobs => self.open_frames(),
+                KeyCode::Char('h') | KeyCode::Left if self.view == View::Frames => {
+                    self.move_frame_cursor(-1)
+                }
+                KeyCode::Char('l') | KeyCode::Right if self.view == View::Frames => {
+                    self.move_frame_cursor(1)
+                }
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(self.can_quit()),
+                KeyCode::Tab => self.toggle_view(),
                 KeyCode::Char('j') | KeyCode::Down => self.next(),
                 KeyCode::Char('k') | KeyCode::Up => self.previous(),
                 KeyCode::Char('D') => self.delete_key(), // Kill jobs
+                KeyCode::Char('x') => self.delete_pod_key(), // Kill pods
+                KeyCode::Char(' ') => self.toggle_mark(),
+                KeyCode::Char('V') => self.visual_key(),
+                KeyCode::Char('*') => self.mark_all(),
+                KeyCode::Char('e') => self.export_key(), // Write pods to a CSV file
+                KeyCode::Char('R') => self.retry_key(),  // Re-run failed frames
+                KeyCode::Char('z') => self.suspend_key(), // Suspend or resume jobs
+                KeyCode::Char('P') => self.parallelism_key(), // Throttle jobs
                 KeyCode::Char('o') => self.checkout_key(false), // Set is schedulable false
                 KeyCode::Char('p') => self.checkout_key(true), // Set is schedulable true
-                KeyCode::Char('y') => {
-                    if self.confirmation_popup {
-                        self.yes_key()
-                    }
-                }
-                KeyCode::Char('n') => {
-                    if self.confirmation_popup {
-                        self.no_key()
-                    }
-                }
+                KeyCode::Char('y') if self.confirmation_popup => self.yes_key(),
+                KeyCode::Char('n') if self.confirmation_popup => self.no_key(),
+                KeyCode::Char('d') if self.confirmation_popup => self.dry_run_key(),
+                KeyCode::Char('a') if self.confirmation_popup => self.avoid_key(),
+                KeyCode::Char(':') => self.command = Some(String::new()),
+                KeyCode::Char('/') if self.view == View::Pods => self.filter_key(),
+                KeyCode::Char('m') => self.toggle_mine(),
+                KeyCode::Char('s') if self.view == View::Pods => self.cycle_sort(),
+                KeyCode::Char('S') if self.view == View::Pods => self.reverse_sort(),
                 _ => {}
             },
             // Keybinds while in log mode
             Mode::Logs { pod: _, start: _ } => {
                 match key.code {
+                    KeyCode::Esc if self.log_search.is_some() => self.log_search = None,
                     KeyCode::Esc | KeyCode::Char('q') => self.exit_log_mode(),
-                    KeyCode::Char('k') | KeyCode::Up => self.scroll_logs(false),
-                    KeyCode::Char('j') | KeyCode::Down => self.scroll_logs(true),
+                    KeyCode::Char('/') => self.search_key(false),
+                    KeyCode::Char('?') => self.search_key(true),
+                    KeyCode::Char('n') => self.next_match(false),
+                    KeyCode::Char('N') => self.next_match(true),
+                    KeyCode::Char('&') => self.toggle_log_filter(),
+                    KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.page_logs(-0.5)
+                    }
+                    KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.page_logs(0.5)
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => self.scroll_logs(-1),
+                    KeyCode::Char('j') | KeyCode::Down => self.scroll_logs(1),
+                    KeyCode::PageUp => self.page_logs(-1.0),
+                    KeyCode::PageDown => self.page_logs(1.0),
+                    KeyCode::Char('g') | KeyCode::Home => self.logs_top(),
+                    KeyCode::Char('G') | KeyCode::End => self.logs_bottom(),
+                    KeyCode::Char('f') => self.toggle_follow(),
+                    KeyCode::Char('w') => self.toggle_wrap(),
+                    KeyCode::Char('h') | KeyCode::Left => self.hscroll_logs(-8),
+                    KeyCode::Char('l') | KeyCode::Right => self.hscroll_logs(8),
+                    KeyCode::Char('#') => self.log_gutter = !self.log_gutter,
+                    KeyCode::Char('c') => self.container_key(),
+                    KeyCode::Char('p') => self.toggle_previous_logs(),
                     _ => {}
                 }
                 return Ok(false);
             }
+            // Keybinds while in the message history
+            Mode::Messages => match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => self.mode = Mode::Table,
+                KeyCode::Char('j') | KeyCode::Down => self.messages_state.select_next(),
+                KeyCode::Char('k') | KeyCode::Up => self.messages_state.select_previous(),
+                _ => {}
+            },
         }
         Ok(false)
     }
 }
+
+/// Wait for the next line from the log stream, or forever when no logs are open
+async fn next_log_line(log_rx: &mut Option<UnboundedReceiver<String>>) -> Option<String> {
+    match log_rx {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
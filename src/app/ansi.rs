@@ -0,0 +1,197 @@
+use ratatui::style::{Color, Modifier, Style};
+
+/// Colours of `30`-`37` and `40`-`47`
+const NORMAL: [Color; 8] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+];
+/// Colours of `90`-`97` and `100`-`107`
+const BRIGHT: [Color; 8] = [
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+/// Split a line a tool coloured itself into the text a terminal would show and the styles its
+/// escape codes set. Each style applies from its byte offset in the text up to the next one.
+/// Colours and text attributes are kept, other escape sequences and control characters are
+/// dropped, and a carriage return starts the line over like it would in a terminal.
+pub fn parse(raw: &str) -> (String, Vec<(usize, Style)>) {
+    let mut text = String::with_capacity(raw.len());
+    let mut styles: Vec<(usize, Style)> = Vec::new();
+    let mut style = Style::default();
+    let mut returned = false;
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                // Control sequence: parameters, then a final byte
+                Some('[') => {
+                    let mut params = String::new();
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            if c == 'm' {
+                                style = sgr(style, &params);
+                            }
+                            break;
+                        }
+                        params.push(c);
+                    }
+                }
+                // Operating system command, e.g. a window title or hyperlink
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\r' => returned = true,
+            // Tabs would be drawn one cell wide and throw off wrapping
+            '\t' | ' '.. if c != '\x7f' => {
+                if returned {
+                    text.clear();
+                    styles.retain(|&(at, _)| at == 0);
+                    returned = false;
+                }
+                match styles.last_mut() {
+                    Some((at, last)) if *at == text.len() => *last = style,
+                    Some((_, last)) if *last == style => {}
+                    None if style == Style::default() => {}
+                    _ => styles.push((text.len(), style)),
+                }
+                if c == '\t' {
+                    text.push_str("    ");
+                } else {
+                    text.push(c);
+                }
+            }
+            _ => {}
+        }
+    }
+    (text, styles)
+}
+
+/// Apply the parameters of a Select Graphic Rendition sequence, `ESC [ ... m`
+fn sgr(mut style: Style, params: &str) -> Style {
+    let mut codes = params
+        .split([';', ':'])
+        .map(|code| code.parse::<u16>().unwrap_or_default());
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            25 => style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(NORMAL[usize::from(code - 30)]),
+            38 => match extended_color(&mut codes) {
+                Some(color) => style.fg(color),
+                None => style,
+            },
+            39 => Style { fg: None, ..style },
+            40..=47 => style.bg(NORMAL[usize::from(code - 40)]),
+            48 => match extended_color(&mut codes) {
+                Some(color) => style.bg(color),
+                None => style,
+            },
+            49 => Style { bg: None, ..style },
+            90..=97 => style.fg(BRIGHT[usize::from(code - 90)]),
+            100..=107 => style.bg(BRIGHT[usize::from(code - 100)]),
+            _ => style,
+        };
+    }
+    style
+}
+
+/// The colour after a `38` or `48`: `5;n` from the 256 colour palette or `2;r;g;b`
+fn extended_color(codes: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    let mut byte = || codes.next().and_then(|code| u8::try_from(code).ok());
+    match byte()? {
+        5 => Some(Color::Indexed(byte()?)),
+        2 => Some(Color::Rgb(byte()?, byte()?, byte()?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sgr_codes_set_styles_from_where_they_appear() {
+        let (text, styles) = parse("plain \x1b[1;32mok\x1b[0m done");
+        assert_eq!(text, "plain ok done");
+        assert_eq!(
+            styles,
+            [
+                (
+                    6,
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD)
+                ),
+                (8, Style::default()),
+            ]
+        );
+    }
+
+    #[test]
+    fn extended_colours() {
+        let (_, styles) = parse("\x1b[38;5;208morange\x1b[48;2;10;20;30m on rgb");
+        assert_eq!(
+            styles,
+            [
+                (0, Style::default().fg(Color::Indexed(208))),
+                (
+                    6,
+                    Style::default()
+                        .fg(Color::Indexed(208))
+                        .bg(Color::Rgb(10, 20, 30))
+                ),
+            ]
+        );
+        // Colon separated, as some tools print them
+        let (_, styles) = parse("\x1b[38:5:9mred");
+        assert_eq!(styles, [(0, Style::default().fg(Color::Indexed(9)))]);
+    }
+
+    #[test]
+    fn carriage_return_starts_the_line_over() {
+        let (text, styles) = parse("\x1b[33mprogress 10%\rprogress 99%");
+        assert_eq!(text, "progress 99%");
+        assert_eq!(styles, [(0, Style::default().fg(Color::Yellow))]);
+        // A trailing one, as from CRLF line endings, changes nothing
+        assert_eq!(parse("done\r").0, "done");
+    }
+
+    #[test]
+    fn other_escapes_and_control_characters_are_dropped() {
+        let link = "\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x07";
+        assert_eq!(parse(link), ("link".to_string(), Vec::new()));
+        assert_eq!(parse("a\x1b[2Kb\x08c\x7f").0, "abc");
+        assert_eq!(parse("a\tb").0, "a    b");
+    }
+}
@@ -0,0 +1,322 @@
+use super::table::{format_age, format_run_time};
+use crate::data::Data;
+
+use k8s_openapi::chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Narrowest the name column gets before other columns are dropped to make room
+const NAME_MIN_WIDTH: u16 = 16;
+/// Widest any other column gets, longer values are truncated
+const MAX_WIDTH: u16 = 24;
+
+/// A column of the pod table, configured in `columns` as e.g. `"status"` or `"label:show"`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Column {
+    Name,
+    Status,
+    Phase,
+    Artist,
+    Node,
+    Job,
+    RunTime,
+    Age,
+    Label(String),
+    Annotation(String),
+}
+
+/// Columns `s` cycles the pod table's sort through, the first is the default
+pub const SORT_COLUMNS: [Column; 5] = [
+    Column::Age,
+    Column::Status,
+    Column::Artist,
+    Column::Node,
+    Column::RunTime,
+];
+
+impl Column {
+    pub fn default_set() -> Vec<Self> {
+        vec![
+            Column::Name,
+            Column::Status,
+            Column::Artist,
+            Column::Node,
+            Column::RunTime,
+            Column::Age,
+        ]
+    }
+
+    pub fn header(&self) -> &str {
+        match self {
+            Column::Name => "Name",
+            Column::Status => "Status",
+            Column::Phase => "Phase",
+            Column::Artist => "Artist",
+            Column::Node => "Node",
+            Column::Job => "Job",
+            Column::RunTime => "Run Time",
+            Column::Age => "Age",
+            // Headed by the key without its prefix, e.g. `show` for `oom/show`
+            Column::Label(key) | Column::Annotation(key) => key.rsplit('/').next().unwrap_or(key),
+        }
+    }
+
+    /// What the column shows for a pod
+    pub fn text(&self, pod: &Data) -> String {
+        match self {
+            Column::RunTime => pod
+                .started_at
+                .as_ref()
+                .map(|s| format_run_time(s, &pod.finished_at.unwrap_or_else(Utc::now)))
+                .unwrap_or_else(|| "n/a".into()),
+            Column::Age => pod
+                .created_at
+                .as_ref()
+                .map(format_age)
+                .unwrap_or_else(|| "n/a".into()),
+            _ => self.value(pod).unwrap_or_default().to_string(),
+        }
+    }
+
+    /// What a text column shows for a pod, borrowed so sorting doesn't allocate. `None` for the
+    /// time columns, which are formatted.
+    fn value<'a>(&self, pod: &'a Data) -> Option<&'a str> {
+        match self {
+            Column::Name => Some(&pod.name),
+            Column::Status => Some(&pod.status),
+            Column::Phase => Some(&pod.phase),
+            Column::Artist => Some(&pod.artist),
+            Column::Node => Some(&pod.node),
+            Column::Job => Some(pod.controller.as_deref().unwrap_or_default()),
+            Column::Label(key) => Some(pod.labels.get(key).map_or("", String::as_str)),
+            Column::Annotation(key) => Some(pod.annotations.get(key).map_or("", String::as_str)),
+            Column::RunTime | Column::Age => None,
+        }
+    }
+
+    /// Order of two pods when sorting by this column: newest and longest running first for
+    /// times, alphabetical otherwise. Pods without a time go last. `now` is when the sort
+    /// started, so pods still running are compared against the same time.
+    pub fn compare(&self, a: &Data, b: &Data, now: DateTime<Utc>) -> Ordering {
+        match self {
+            Column::Age => newest_first(a.created_at, b.created_at),
+            Column::RunTime => newest_first(run_time(a, now), run_time(b, now)),
+            _ => self.value(a).cmp(&self.value(b)),
+        }
+    }
+}
+
+/// Descending, with `None` after everything else
+fn newest_first<T: Ord>(a: Option<T>, b: Option<T>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => b.cmp(&a),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn run_time(pod: &Data, now: DateTime<Utc>) -> Option<TimeDelta> {
+    Some(pod.finished_at.unwrap_or(now) - pod.started_at?)
+}
+
+impl TryFrom<String> for Column {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if let Some(key) = value.strip_prefix("label:") {
+            return Ok(Column::Label(key.to_string()));
+        }
+        if let Some(key) = value.strip_prefix("annotation:") {
+            return Ok(Column::Annotation(key.to_string()));
+        }
+        match value.as_str() {
+            "name" => Ok(Column::Name),
+            "status" => Ok(Column::Status),
+            "phase" => Ok(Column::Phase),
+            "artist" => Ok(Column::Artist),
+            "node" => Ok(Column::Node),
+            "job" => Ok(Column::Job),
+            "run_time" => Ok(Column::RunTime),
+            "age" => Ok(Column::Age),
+            other => Err(format!(
+                "unknown column '{other}', expected name, status, phase, artist, node, job, \
+                 run_time, age, label:<key> or annotation:<key>"
+            )),
+        }
+    }
+}
+
+impl From<Column> for String {
+    fn from(column: Column) -> Self {
+        column.to_string()
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Column::Name => f.write_str("name"),
+            Column::Status => f.write_str("status"),
+            Column::Phase => f.write_str("phase"),
+            Column::Artist => f.write_str("artist"),
+            Column::Node => f.write_str("node"),
+            Column::Job => f.write_str("job"),
+            Column::RunTime => f.write_str("run_time"),
+            Column::Age => f.write_str("age"),
+            Column::Label(key) => write!(f, "label:{key}"),
+            Column::Annotation(key) => write!(f, "annotation:{key}"),
+        }
+    }
+}
+
+/// Columns that fit in `width` cells, with the width of each. Each column asks for room for its
+/// widest value, up to [`MAX_WIDTH`]. When that doesn't fit, columns are dropped from the end of
+/// the list until it does, but the name column is kept and gets whatever room is left.
+pub fn fit(columns: &[Column], rows: &[Vec<String>], width: u16) -> Vec<(usize, u16)> {
+    let wanted: Vec<u16> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let widest = rows
+                .iter()
+                .map(|row| row[i].width())
+                .max()
+                .unwrap_or_default()
+                // Room for the sort arrow
+                .max(column.header().width() + 2);
+            match column {
+                Column::Name => NAME_MIN_WIDTH,
+                _ => (widest as u16).min(MAX_WIDTH),
+            }
+        })
+        .collect();
+    let mut kept: Vec<usize> = (0..columns.len()).collect();
+    let needed = |kept: &[usize]| {
+        let gaps = kept.len().saturating_sub(1) as u16;
+        kept.iter().map(|&i| wanted[i]).sum::<u16>() + gaps
+    };
+    while needed(&kept) > width {
+        let Some(last) = kept.iter().rposition(|&i| columns[i] != Column::Name) else {
+            break;
+        };
+        kept.remove(last);
+    }
+    let spare = width.saturating_sub(needed(&kept));
+    kept.into_iter()
+        .map(|i| match columns[i] {
+            Column::Name => (i, wanted[i] + spare),
+            _ => (i, wanted[i]),
+        })
+        .collect()
+}
+
+/// Cut text down to `width` terminal cells, ending in an ellipsis when anything was cut
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or_default();
+        if used + char_width + 1 > width {
+            break;
+        }
+        out.push(c);
+        used += char_width;
+    }
+    if width > 0 {
+        out.push('…');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pod(name: &str, started: Option<i64>, finished: Option<i64>) -> Data {
+        let at = |secs| DateTime::from_timestamp(secs, 0);
+        Data {
+            name: name.into(),
+            started_at: started.and_then(at),
+            finished_at: finished.and_then(at),
+            created_at: started.and_then(at),
+            ..Data::default()
+        }
+    }
+
+    fn sorted(column: &Column, mut pods: Vec<Data>) -> Vec<String> {
+        let now = DateTime::from_timestamp(1000, 0).unwrap();
+        pods.sort_by(|a, b| column.compare(a, b, now));
+        pods.into_iter().map(|pod| pod.name).collect()
+    }
+
+    #[test]
+    fn run_time_sorts_longest_first_and_unstarted_last() {
+        let pods = vec![
+            pod("pending", None, None),
+            pod("short", Some(900), Some(950)),
+            pod("running", Some(100), None),
+            pod("long", Some(0), Some(500)),
+        ];
+        assert_eq!(
+            sorted(&Column::RunTime, pods),
+            ["running", "long", "short", "pending"]
+        );
+    }
+
+    #[test]
+    fn age_sorts_newest_first() {
+        let pods = vec![
+            pod("old", Some(0), None),
+            pod("unknown", None, None),
+            pod("new", Some(900), None),
+        ];
+        assert_eq!(sorted(&Column::Age, pods), ["new", "old", "unknown"]);
+    }
+
+    #[test]
+    fn text_columns_sort_alphabetically() {
+        let pods = vec![
+            pod("b", None, None),
+            pod("c", None, None),
+            pod("a", None, None),
+        ];
+        assert_eq!(sorted(&Column::Name, pods), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn truncate_counts_wide_characters_as_two_cells() {
+        assert_eq!(truncate("ショット", 8), "ショット");
+        assert_eq!(truncate("ショット", 7), "ショッ…");
+        assert_eq!(truncate("ショット", 6), "ショ…");
+        assert_eq!(truncate("shot010", 4), "sho…");
+        assert_eq!(truncate("shot010", 0), "");
+    }
+
+    #[test]
+    fn fit_measures_wide_characters_in_cells() {
+        let columns = [Column::Name, Column::Artist];
+        let rows = vec![vec!["p".to_string(), "アーティスト".to_string()]];
+        // The artist is six wide characters, twelve cells
+        assert_eq!(fit(&columns, &rows, 40), [(0, 27), (1, 12)]);
+    }
+
+    #[test]
+    fn fit_drops_columns_from_the_end_but_keeps_the_name() {
+        let columns = [Column::Status, Column::Name, Column::Node];
+        let rows = vec![vec![
+            "Running".to_string(),
+            "p".to_string(),
+            "workstation-01".to_string(),
+        ]];
+        assert_eq!(fit(&columns, &rows, 30), [(0, 8), (1, 21)]);
+        // The name keeps its minimum even when that overflows
+        assert_eq!(fit(&columns, &rows, 10), [(1, 16)]);
+    }
+}
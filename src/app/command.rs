@@ -0,0 +1,39 @@
+use super::App;
+use super::notifications::Level;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+
+impl App {
+    /// Keybinds while typing a `:` command. Returns true when the command asks to quit.
+    pub fn handle_command_key(&mut self, key: KeyEvent) -> bool {
+        let Some(command) = self.command.as_mut() else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Esc => self.command = None,
+            KeyCode::Enter => {
+                let command = self.command.take().unwrap_or_default();
+                return self.run_command(command.trim());
+            }
+            KeyCode::Backspace if command.is_empty() => self.command = None,
+            KeyCode::Backspace => {
+                command.pop();
+            }
+            KeyCode::Char(c) => command.push(c),
+            _ => {}
+        }
+        false
+    }
+
+    /// Run a `:` command. Returns true when the command asks to quit.
+    fn run_command(&mut self, command: &str) -> bool {
+        match command {
+            "" => {}
+            "q" | "quit" => return self.can_quit(),
+            "messages" | "mes" => self.open_messages(),
+            "debug" => self.show_debug = !self.show_debug,
+            other => self.notify(Level::Error, format!("Unknown command: {other}")),
+        }
+        false
+    }
+}
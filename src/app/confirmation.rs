@@ -1,14 +1,139 @@
 use super::App;
+use super::notifications::Level;
+use crate::data::JobData;
+use crate::k8s::label_safe_artist;
 
+use itertools::Itertools;
 use ratatui::{
     Frame,
     layout::{Constraint, Flex, Layout, Rect},
-    widgets::{Block, Clear, Paragraph},
+    widgets::{Block, Clear, Paragraph, Wrap},
 };
 
+/// Rows listed in the popup before it elides the rest
+const MAX_LISTED: usize = 10;
+
 pub enum ConfirmAction {
-    CancelJob { controller: String },
-    CheckoutNode { schedulable: bool },
+    /// Delete exactly these Jobs, resolved from the controllers when the popup opened
+    CancelJob {
+        controllers: Vec<String>,
+        jobs: Vec<JobData>,
+    },
+    CheckoutNode {
+        schedulable: bool,
+    },
+    SuspendJobs {
+        controllers: Vec<String>,
+    },
+    ResumeJobs {
+        controllers: Vec<String>,
+    },
+    RetryFrames {
+        job: String,
+        frames: Vec<u32>,
+        /// Failed frames left out to keep to the job's parallelism, retried with `R` later
+        left: usize,
+    },
+    /// Delete pods for their Jobs to recreate
+    DeletePods {
+        pods: Vec<PodTarget>,
+    },
+}
+
+/// A pod to delete, with the node its artist could avoid from then on
+pub struct PodTarget {
+    pub name: String,
+    /// Unset while the pod isn't scheduled
+    pub node: Option<String>,
+    pub artist: String,
+}
+
+impl ConfirmAction {
+    /// What is about to happen, shown above the y/n prompt
+    fn describe(&self) -> String {
+        match self {
+            ConfirmAction::CancelJob { controllers, jobs } => {
+                let running: i32 = jobs.iter().map(|job| job.active).sum();
+                let mut text = format!(
+                    "Cancel {} job(s) of {}, stopping {running} running pod(s):\n",
+                    jobs.len(),
+                    controllers.join(", ")
+                );
+                text.push_str(&listed(jobs.iter().map(|job| {
+                    format!(
+                        "{} ({}) - {} running, {} done, {} failed pod(s)",
+                        job.name,
+                        job.artist.as_deref().unwrap_or("Unknown"),
+                        job.active,
+                        job.succeeded,
+                        job.failed
+                    )
+                })));
+                text
+            }
+            ConfirmAction::CheckoutNode { schedulable: true } => {
+                "Return your node to the farm.".to_string()
+            }
+            ConfirmAction::CheckoutNode { schedulable: false } => {
+                "Check your node out of the farm.".to_string()
+            }
+            ConfirmAction::SuspendJobs { controllers } => format!(
+                "Suspend the jobs of {}. Their running pods are stopped and lose progress.",
+                controllers.join(", ")
+            ),
+            ConfirmAction::ResumeJobs { controllers } => {
+                format!("Resume the jobs of {}.", controllers.join(", "))
+            }
+            ConfirmAction::RetryFrames { job, frames, left } => {
+                let mut text = format!(
+                    "Re-run {} failed frame(s) of {job} as {} new Job(s) of one pod each, all \
+                     starting at once:\n{}",
+                    frames.len(),
+                    frames.len(),
+                    frames.iter().join(", ")
+                );
+                if *left > 0 {
+                    text.push_str(&format!(
+                        "\n{left} more failed frame(s) are left for later, to keep to the \
+                         job's parallelism. Press (R) again once these are done."
+                    ));
+                }
+                text
+            }
+            ConfirmAction::DeletePods { pods } => {
+                let mut text = match pods.as_slice() {
+                    [pod] => format!("Delete pod {}, its job will start a new one.", pod.name),
+                    _ => format!(
+                        "Delete {} pods, their jobs will start new ones:\n{}",
+                        pods.len(),
+                        listed(pods.iter().map(|pod| pod.name.clone()))
+                    ),
+                };
+                let avoid = avoided_nodes(pods);
+                if !avoid.is_empty() {
+                    let pairs = avoid
+                        .iter()
+                        .map(|(node, artist)| format!("{artist}'s pods off {node}"));
+                    text.push_str(&format!(
+                        "\n\n(a) also keeps {} from now on.",
+                        pairs.take(MAX_LISTED).join(", ")
+                    ));
+                }
+                text
+            }
+        }
+    }
+
+    /// Answers the popup takes
+    fn keys(&self) -> &'static str {
+        match self {
+            ConfirmAction::CancelJob { .. } => "(y) yes, (d) dry run, (n) no",
+            ConfirmAction::DeletePods { pods } if !avoided_nodes(pods).is_empty() => {
+                "(y) yes, (a) yes and avoid the node, (n) no"
+            }
+            _ => "(y/n)",
+        }
+    }
 }
 
 impl App {
@@ -16,32 +141,111 @@ impl App {
         if self.confirmation_popup {
             let area = frame.area();
             let block = Block::bordered().title("Confirmation");
-            let text = "\nAre you sure?\n\n(y/n)";
-            let content = Paragraph::new(text).centered().block(block);
-            let area = popup_area(area, 60, 20);
+            let Some(action) = self.pending_confirmation.as_ref() else {
+                return;
+            };
+            let text = format!(
+                "\n{}\n\nAre you sure?\n\n{}",
+                action.describe(),
+                action.keys()
+            );
+            // Grow with the text, e.g. a long list of jobs
+            let height = text.lines().count() as u16 + 3;
+            let content = Paragraph::new(text)
+                .centered()
+                .wrap(Wrap { trim: false })
+                .block(block);
+            let area = popup_area(area, 60, height);
             frame.render_widget(Clear, area);
             frame.render_widget(content, area);
         }
     }
 
+    /// Cancellations wait in the undo queue first, everything else runs right away
     pub fn yes_key(&mut self) {
         self.confirmation_popup = false;
-        if let Some(action) = self.pending_confirmation.take() {
-            match action {
-                ConfirmAction::CancelJob { controller } => self.run_cancel_jobs(controller),
-                ConfirmAction::CheckoutNode { schedulable } => self.run_checkout(schedulable),
+        match self.pending_confirmation.take() {
+            Some(action @ ConfirmAction::CancelJob { .. }) => self.queue_action(action),
+            Some(action) => self.run_action(action),
+            None => {}
+        }
+    }
+
+    /// Carry out a confirmed action
+    pub fn run_action(&mut self, action: ConfirmAction) {
+        match action {
+            ConfirmAction::CancelJob { controllers, jobs } => {
+                self.run_cancel_jobs(controllers, jobs, false)
             }
+            ConfirmAction::CheckoutNode { schedulable } => self.run_checkout(schedulable),
+            ConfirmAction::SuspendJobs { controllers } => self.run_suspend_jobs(controllers, true),
+            ConfirmAction::ResumeJobs { controllers } => self.run_suspend_jobs(controllers, false),
+            ConfirmAction::RetryFrames { job, frames, .. } => self.run_retry_frames(job, frames),
+            ConfirmAction::DeletePods { pods } => self.run_delete_pods(pods, false),
+        }
+    }
+
+    /// Let the API server check a cancellation without deleting anything
+    pub fn dry_run_key(&mut self) {
+        if !matches!(
+            self.pending_confirmation,
+            Some(ConfirmAction::CancelJob { .. })
+        ) {
+            return;
+        }
+        self.confirmation_popup = false;
+        if let Some(ConfirmAction::CancelJob { controllers, jobs }) =
+            self.pending_confirmation.take()
+        {
+            self.run_cancel_jobs(controllers, jobs, true);
+        }
+    }
+
+    /// Delete the pods and keep their artists' pods off their nodes
+    pub fn avoid_key(&mut self) {
+        if !matches!(
+            self.pending_confirmation,
+            Some(ConfirmAction::DeletePods { .. })
+        ) {
+            return;
+        }
+        self.confirmation_popup = false;
+        if let Some(ConfirmAction::DeletePods { pods }) = self.pending_confirmation.take() {
+            self.run_delete_pods(pods, true);
         }
     }
 
     pub fn no_key(&mut self) {
         self.confirmation_popup = false;
-        self.pending_confirmation = None;
+        if self.pending_confirmation.take().is_some() {
+            self.notify(Level::Info, "Aborted");
+        }
     }
 }
 
-fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
-    let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
+/// One row per line, eliding everything past [`MAX_LISTED`]
+fn listed(rows: impl ExactSizeIterator<Item = String>) -> String {
+    let total = rows.len();
+    let mut text = rows.take(MAX_LISTED).map(|row| format!("\n{row}")).join("");
+    if total > MAX_LISTED {
+        text.push_str(&format!("\n...and {} more", total - MAX_LISTED));
+    }
+    text
+}
+
+/// Distinct (node, artist) pairs the pods ran on, for artists that can go into a node label
+pub fn avoided_nodes(pods: &[PodTarget]) -> Vec<(String, String)> {
+    pods.iter()
+        .filter(|pod| label_safe_artist(&pod.artist).is_some())
+        .filter_map(|pod| Some((pod.node.clone()?, pod.artist.clone())))
+        .unique()
+        .collect()
+}
+
+/// Centered area `percent_x` wide and at least `height` rows high
+pub fn popup_area(area: Rect, percent_x: u16, height: u16) -> Rect {
+    let height = height.max(area.height / 5);
+    let vertical = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center);
     let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
     let [area] = vertical.areas(area);
     let [area] = horizontal.areas(area);
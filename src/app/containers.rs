@@ -0,0 +1,169 @@
+use super::App;
+use super::Mode;
+use super::confirmation::popup_area;
+use super::notifications::Level;
+use crate::data::Data;
+use crate::k8s::DEFAULT_CONTAINER_ANNOTATION;
+
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEvent},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListItem, ListState},
+};
+
+/// Container `kubectl logs` would show: the one the pod names as its default, or else its first
+/// container that isn't an init container
+pub fn default_container(pod: &Data) -> Option<String> {
+    pod.annotations
+        .get(DEFAULT_CONTAINER_ANNOTATION)
+        .cloned()
+        .or_else(|| {
+            pod.containers
+                .iter()
+                .find(|container| !container.init)
+                .map(|container| container.name.clone())
+        })
+}
+
+impl App {
+    /// Pod whose logs are open
+    fn log_pod(&self) -> Option<&Data> {
+        let Mode::Logs { pod, .. } = &self.mode else {
+            return None;
+        };
+        self.items.iter().find(|p| &p.name == pod)
+    }
+
+    /// Open the picker of the containers whose logs can be shown
+    pub fn container_key(&mut self) {
+        let Some(pod) = self.log_pod() else {
+            self.notify(
+                Level::Info,
+                "The pod is gone, its containers can't be listed",
+            );
+            return;
+        };
+        if pod.containers.len() < 2 {
+            let name = pod.name.clone();
+            self.notify(Level::Info, format!("{name} has only one container"));
+            return;
+        }
+        let current = pod
+            .containers
+            .iter()
+            .position(|container| self.log_container.as_ref() == Some(&container.name));
+        self.container_picker = Some(ListState::default().with_selected(current.or(Some(0))));
+    }
+
+    /// Keybinds while picking a container
+    pub fn handle_container_key(&mut self, key: KeyEvent) {
+        let count = self.log_pod().map_or(0, |pod| pod.containers.len());
+        let Some(state) = self.container_picker.as_mut() else {
+            return;
+        };
+        let selected = state.selected().unwrap_or_default();
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.container_picker = None,
+            KeyCode::Char('j') | KeyCode::Down => {
+                state.select(Some((selected + 1).min(count.saturating_sub(1))))
+            }
+            KeyCode::Char('k') | KeyCode::Up => state.select(Some(selected.saturating_sub(1))),
+            KeyCode::Enter => {
+                self.container_picker = None;
+                let Some(name) = self
+                    .log_pod()
+                    .and_then(|pod| pod.containers.get(selected))
+                    .map(|container| container.name.clone())
+                else {
+                    return;
+                };
+                if self.log_container.as_ref() != Some(&name) || self.log_previous {
+                    self.log_container = Some(name);
+                    self.log_previous = false;
+                    self.open_log_stream();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Switch between the logs of the container's current run and those of its previous one,
+    /// e.g. to see why it crashed
+    pub fn toggle_previous_logs(&mut self) {
+        let container = self.log_container.clone().unwrap_or_default();
+        let restarts = self.log_pod().and_then(|pod| {
+            pod.containers
+                .iter()
+                .find(|c| c.name == container)
+                .map(|c| c.restarts)
+        });
+        if !self.log_previous && restarts == Some(0) {
+            self.notify(
+                Level::Info,
+                format!("{container} hasn't restarted, there are no previous logs"),
+            );
+            return;
+        }
+        self.log_previous = !self.log_previous;
+        self.open_log_stream();
+    }
+
+    /// Title text for whose logs are shown, e.g. `, container render, previous run`
+    pub fn log_source_label(&self) -> String {
+        let mut label = String::new();
+        if let Some(container) = &self.log_container {
+            label.push_str(&format!(", container {container}"));
+        }
+        if self.log_previous {
+            label.push_str(", previous run");
+        }
+        label
+    }
+
+    /// Popup listing the open pod's containers, with their restarts and state
+    pub fn draw_container_picker(&mut self, frame: &mut Frame) {
+        let Some(pod) = self.log_pod().filter(|_| self.container_picker.is_some()) else {
+            return;
+        };
+        let width = pod
+            .containers
+            .iter()
+            .map(|container| container.name.len())
+            .max()
+            .unwrap_or_default();
+        let items: Vec<ListItem> = pod
+            .containers
+            .iter()
+            .map(|container| {
+                let current = self.log_container.as_ref() == Some(&container.name);
+                let kind = if container.init { "init" } else { "" };
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!(
+                        "{} {:<width$}  {kind:<4}  ",
+                        if current { '*' } else { ' ' },
+                        container.name,
+                    )),
+                    Span::styled(
+                        format!("{} restart(s), {}", container.restarts, container.state),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]))
+            })
+            .collect();
+        let height = items.len() as u16 + 2;
+        let list = List::new(items)
+            .block(
+                Block::bordered()
+                    .title(format!("Containers of {}", pod.name))
+                    .title_bottom("(Enter) show logs, (Esc) close"),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let area = popup_area(frame.area(), 60, height);
+        frame.render_widget(Clear, area);
+        if let Some(state) = self.container_picker.as_mut() {
+            frame.render_stateful_widget(list, area, state);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use super::App;
+use crate::telemetry;
+
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Cell, Clear, Paragraph, Row, Table},
+};
+use std::time::Duration;
+
+impl App {
+    /// Debug overlay with API call latencies, error counts and watch state, toggled with F12
+    pub fn draw_debug(&self, frame: &mut Frame) {
+        if !self.show_debug {
+            return;
+        }
+        let stats = telemetry::snapshot();
+        let mut rows: Vec<Row> = stats
+            .calls
+            .iter()
+            .map(|(name, call)| {
+                let style = if call.errors > 0 {
+                    Style::default().fg(Color::Red)
+                } else {
+                    Style::default()
+                };
+                Row::new(vec![
+                    Cell::from(*name),
+                    Cell::from(call.count.to_string()),
+                    Cell::from(call.errors.to_string()),
+                    Cell::from(format_ms(call.last)),
+                    Cell::from(format_ms(call.average())),
+                    Cell::from(format_ms(call.max)),
+                ])
+                .style(style)
+            })
+            .collect();
+        if rows.is_empty() {
+            rows.push(Row::new(vec![Cell::from("(no API calls yet)")]));
+        }
+        let mut footer = Vec::new();
+        for (kind, watch) in &stats.watches {
+            footer.push(Line::from(format!(
+                "{kind} watch: {:?}, {} events, {} errors",
+                watch.state, watch.events, watch.errors
+            )));
+            if let Some(error) = &watch.last_error {
+                footer.push(Line::styled(
+                    format!("last {kind} watch error: {error}"),
+                    Style::default().fg(Color::Red),
+                ));
+            }
+        }
+        footer.push(Line::styled(
+            match &stats.log_dir {
+                Some(dir) => format!("log files: {}", dir.display()),
+                None => "file logging disabled".to_string(),
+            },
+            Style::default().fg(Color::DarkGray),
+        ));
+
+        let height = (rows.len() + footer.len()) as u16 + 4;
+        let vertical = Layout::vertical([Constraint::Length(height)]).flex(Flex::End);
+        let horizontal = Layout::horizontal([Constraint::Percentage(70)]).flex(Flex::End);
+        let [area] = vertical.areas(frame.area());
+        let [area] = horizontal.areas(area);
+        let block = Block::bordered()
+            .title("Debug - (F12) to close")
+            .border_style(Style::default().fg(Color::Yellow));
+        let inner = block.inner(area);
+        let [table_area, footer_area] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(footer.len() as u16)])
+                .areas(inner);
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(3),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(["Call", "Count", "Errors", "Last", "Avg", "Max"]));
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+        frame.render_widget(table, table_area);
+        frame.render_widget(Paragraph::new(footer), footer_area);
+    }
+}
+
+fn format_ms(duration: Duration) -> String {
+    format!("{}ms", duration.as_millis())
+}
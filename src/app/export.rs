@@ -0,0 +1,101 @@
+use super::notifications::Level;
+use super::{App, View};
+use crate::data::Data;
+
+use k8s_openapi::chrono::Local;
+use std::fs;
+
+/// Columns of an exported pod list
+const HEADER: [&str; 6] = [
+    "name",
+    "namespace",
+    "status",
+    "node",
+    "controller",
+    "artist",
+];
+
+impl App {
+    /// Write the marked pods, or else the selected one, to a CSV file in the working directory
+    pub fn export_key(&mut self) {
+        if self.view == View::Jobs {
+            self.notify(Level::Error, "Select pods to export, not a job");
+            return;
+        }
+        let pods = self.target_pods();
+        if pods.is_empty() {
+            return;
+        }
+        let count = pods.len();
+        let csv = pods_csv(&pods, &self.config.namespace);
+        let path = format!("mf-pods-{}.csv", Local::now().format("%Y%m%d-%H%M%S"));
+        match fs::write(&path, csv) {
+            Ok(()) => self.notify(Level::Success, format!("Exported {count} pod(s) to {path}")),
+            Err(e) => self.notify(Level::Error, format!("Failed to write {path}: {e}")),
+        }
+    }
+}
+
+/// One CSV row per pod, after a header row
+fn pods_csv(pods: &[&Data], namespace: &str) -> String {
+    let mut csv = HEADER.join(",") + "\n";
+    for pod in pods {
+        let row = [
+            pod.name.as_str(),
+            namespace,
+            pod.status.as_str(),
+            pod.node.as_str(),
+            pod.controller.as_deref().unwrap_or(""),
+            pod.artist.as_str(),
+        ];
+        let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quote a field that holds a separator, quote or line break, doubling its quotes
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pod(name: &str, controller: Option<&str>, artist: &str) -> Data {
+        Data {
+            name: name.into(),
+            controller: controller.map(Into::into),
+            phase: "Running".into(),
+            status: "Running".into(),
+            artist: artist.into(),
+            node: "node-1".into(),
+            ..Data::default()
+        }
+    }
+
+    #[test]
+    fn csv_rows_follow_the_header() {
+        let a = pod("render-a-0", Some("render-a"), "alice");
+        let b = pod("standalone", None, "bob");
+        assert_eq!(
+            pods_csv(&[&a, &b], "farm"),
+            "name,namespace,status,node,controller,artist\n\
+             render-a-0,farm,Running,node-1,render-a,alice\n\
+             standalone,farm,Running,node-1,,bob\n"
+        );
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
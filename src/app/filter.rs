@@ -0,0 +1,295 @@
+use super::App;
+use crate::data::Data;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use std::collections::BTreeMap;
+
+/// Parsed filter bar text narrowing the pod table, e.g. `artist:alice status:Failed name:~shot010`
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    pub text: String,
+    terms: Vec<Term>,
+}
+
+/// One space separated word of a filter. Every term has to match for a pod to be shown.
+#[derive(Clone, Debug)]
+struct Term {
+    /// Unset for a bare word, which matches any field
+    field: Option<Field>,
+    /// Lowercased, matching is case insensitive
+    value: String,
+    /// Match part of the field rather than all of it, written `field:~value`
+    contains: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Field {
+    Name,
+    Status,
+    Phase,
+    Artist,
+    Node,
+    Job,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "name" | "pod" => Some(Field::Name),
+            "status" => Some(Field::Status),
+            "phase" => Some(Field::Phase),
+            "artist" => Some(Field::Artist),
+            "node" => Some(Field::Node),
+            "job" => Some(Field::Job),
+            _ => None,
+        }
+    }
+
+    fn value(self, pod: &Data) -> &str {
+        match self {
+            Field::Name => &pod.name,
+            Field::Status => &pod.status,
+            Field::Phase => &pod.phase,
+            Field::Artist => &pod.artist,
+            Field::Node => &pod.node,
+            Field::Job => pod.controller.as_deref().unwrap_or_default(),
+        }
+    }
+}
+
+const FIELDS: [Field; 5] = [
+    Field::Name,
+    Field::Status,
+    Field::Artist,
+    Field::Node,
+    Field::Job,
+];
+
+impl Filter {
+    /// Parse filter bar text, expanding `@name` into the preset of that name
+    pub fn parse(text: &str, presets: &BTreeMap<String, String>) -> Result<Self, String> {
+        let mut terms = Vec::new();
+        for word in text.split_whitespace() {
+            if let Some(preset) = word.strip_prefix('@') {
+                let Some(expanded) = presets.get(preset) else {
+                    return Err(format!("No filter preset named '{preset}'"));
+                };
+                // Presets can't refer to each other, so there is no way to loop
+                let preset = Filter::parse(expanded, &BTreeMap::new())
+                    .map_err(|e| format!("In filter preset '{word}': {e}"))?;
+                terms.extend(preset.terms);
+                continue;
+            }
+            let term = match word.split_once(':') {
+                Some((field, value)) => {
+                    let Some(field) = Field::parse(&field.to_lowercase()) else {
+                        return Err(format!(
+                            "Unknown filter field '{field}', use name, status, phase, artist, node or job"
+                        ));
+                    };
+                    let (value, contains) = match value.strip_prefix('~') {
+                        Some(value) => (value, true),
+                        None => (value, false),
+                    };
+                    Term {
+                        field: Some(field),
+                        value: value.to_lowercase(),
+                        contains,
+                    }
+                }
+                None => Term {
+                    field: None,
+                    value: word.to_lowercase(),
+                    contains: true,
+                },
+            };
+            terms.push(term);
+        }
+        Ok(Self {
+            text: text.trim().to_string(),
+            terms,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn matches(&self, pod: &Data) -> bool {
+        self.terms.iter().all(|term| match term.field {
+            Some(field) => term.matches(field.value(pod)),
+            None => FIELDS.iter().any(|field| term.matches(field.value(pod))),
+        })
+    }
+}
+
+impl Term {
+    fn matches(&self, value: &str) -> bool {
+        let value = value.to_lowercase();
+        if self.contains {
+            value.contains(&self.value)
+        } else {
+            value == self.value
+        }
+    }
+}
+
+/// Filter bar being typed into. The pod table follows along as it's typed.
+pub struct FilterInput {
+    pub input: String,
+    /// Why the input doesn't parse, the table keeps the last filter that did
+    pub error: Option<String>,
+    /// Filter to go back to when typing is abandoned
+    previous: Filter,
+}
+
+impl App {
+    /// Open the filter bar on the current filter
+    pub fn filter_key(&mut self) {
+        self.filter_input = Some(FilterInput {
+            input: self.filter.text.clone(),
+            error: None,
+            previous: self.filter.clone(),
+        });
+    }
+
+    /// Keybinds while typing into the filter bar
+    pub fn handle_filter_key(&mut self, key: KeyEvent) {
+        let Some(filter_input) = self.filter_input.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => {
+                if let Some(filter_input) = self.filter_input.take() {
+                    self.keep_selected_pod(|app| app.filter = filter_input.previous);
+                }
+                return;
+            }
+            KeyCode::Enter => {
+                if filter_input.error.is_none() {
+                    self.filter_input = None;
+                }
+                return;
+            }
+            KeyCode::Backspace => {
+                filter_input.input.pop();
+            }
+            KeyCode::Char(c) => filter_input.input.push(c),
+            _ => return,
+        }
+        let input = filter_input.input.clone();
+        let error = self.set_filter(&input).err();
+        if let Some(filter_input) = self.filter_input.as_mut() {
+            filter_input.error = error;
+        }
+    }
+
+    /// Narrow the pod table, keeping the cursor on the same pod where it's still shown
+    fn set_filter(&mut self, text: &str) -> Result<(), String> {
+        let filter = Filter::parse(text, &self.config.filter_presets)?;
+        self.keep_selected_pod(|app| app.filter = filter);
+        Ok(())
+    }
+
+    /// Drop the filter, showing every pod again
+    pub fn clear_filter(&mut self) {
+        self.keep_selected_pod(|app| app.filter = Filter::default());
+    }
+
+    /// Change what the pod table shows, then put the cursor back on the pod it was on. When that
+    /// pod is gone the cursor stays on the same row, or the last one.
+    pub fn keep_selected_pod(&mut self, update: impl FnOnce(&mut Self)) {
+        let selected = self.state.selected();
+        let name = selected
+            .and_then(|i| self.visible_pods().nth(i))
+            .map(|pod| pod.name.clone());
+        update(self);
+        self.update_visible_pods();
+        let position = name.and_then(|name| self.visible_pods().position(|pod| pod.name == name));
+        let len = self.visible_pods().count();
+        let row = match (position, selected) {
+            (Some(row), _) => Some(row),
+            _ if len == 0 => None,
+            (None, row) => Some(row.unwrap_or_default().min(len - 1)),
+        };
+        self.state.select(row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pod(name: &str, status: &str, artist: &str, node: &str, job: &str) -> Data {
+        Data {
+            name: name.into(),
+            status: status.into(),
+            phase: "Running".into(),
+            artist: artist.into(),
+            node: node.into(),
+            controller: Some(job.into()),
+            ..Data::default()
+        }
+    }
+
+    fn parse(text: &str) -> Filter {
+        Filter::parse(text, &BTreeMap::new()).unwrap()
+    }
+
+    #[test]
+    fn field_terms_match_whole_values_case_insensitively() {
+        let filter = parse("artist:Alice status:failed");
+        assert!(filter.matches(&pod("a", "Failed", "alice", "ws-1", "j")));
+        assert!(!filter.matches(&pod("a", "Failed", "alicia", "ws-1", "j")));
+        assert!(!filter.matches(&pod("a", "Running", "alice", "ws-1", "j")));
+    }
+
+    #[test]
+    fn tilde_matches_part_of_a_field() {
+        let filter = parse("name:~shot010");
+        assert!(filter.matches(&pod("comp-shot010-3", "Running", "a", "n", "j")));
+        assert!(!filter.matches(&pod("comp-shot020-3", "Running", "a", "n", "j")));
+    }
+
+    #[test]
+    fn bare_words_match_part_of_any_field() {
+        let filter = parse("ws-4");
+        assert!(filter.matches(&pod("p", "Running", "bob", "ws-41", "sim")));
+        assert!(!filter.matches(&pod("p", "Running", "bob", "ws-12", "sim")));
+        assert!(parse("sim").matches(&pod("p", "Running", "bob", "ws-12", "sim-b")));
+    }
+
+    #[test]
+    fn empty_text_matches_everything() {
+        let filter = parse("   ");
+        assert!(filter.is_empty());
+        assert!(filter.matches(&pod("p", "Running", "bob", "ws-12", "sim")));
+    }
+
+    #[test]
+    fn presets_expand_in_place() {
+        let presets = BTreeMap::from([("failed".to_string(), "status:Failed".to_string())]);
+        let filter = Filter::parse("@failed artist:bob", &presets).unwrap();
+        assert_eq!(filter.text, "@failed artist:bob");
+        assert!(filter.matches(&pod("p", "Failed", "bob", "n", "j")));
+        assert!(!filter.matches(&pod("p", "Running", "bob", "n", "j")));
+        assert!(!filter.matches(&pod("p", "Failed", "alice", "n", "j")));
+    }
+
+    #[test]
+    fn unknown_presets_and_fields_are_errors() {
+        let presets = BTreeMap::from([("bad".to_string(), "colour:red".to_string())]);
+        assert!(
+            Filter::parse("@missing", &presets)
+                .unwrap_err()
+                .contains("missing")
+        );
+        assert!(
+            Filter::parse("@bad", &presets)
+                .unwrap_err()
+                .contains("'colour'")
+        );
+        let error = Filter::parse("colour:red", &BTreeMap::new()).unwrap_err();
+        assert!(error.starts_with("Unknown filter field 'colour'"));
+    }
+}
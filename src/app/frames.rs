@@ -0,0 +1,250 @@
+use super::confirmation::ConfirmAction;
+use super::notifications::Level;
+use super::{App, Message, View};
+use crate::data::{Data, FrameState, JobData, failed_frames, frame_states};
+use crate::k8s::{describe, retry_frames};
+
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+};
+use tracing::Instrument;
+
+/// Width of one frame cell, including the gap to the next
+const CELL_WIDTH: u16 = 6;
+
+impl App {
+    /// Grid of an Indexed Job's completion indexes, one colored cell per frame
+    pub fn draw_frames(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(job) = self.frames_job() else {
+            let block = Block::bordered().title("Frames");
+            frame.render_widget(Paragraph::new("The job is gone").block(block), area);
+            return;
+        };
+        let states = frame_states(job, &self.items);
+        let name = job.name.clone();
+        let count = |state: FrameState| states.iter().filter(|s| **s == state).count();
+        let title = format!(
+            "Frames of {name} - {} done, {} running, {} retrying, {} failed, {} waiting",
+            count(FrameState::Done),
+            count(FrameState::Running),
+            count(FrameState::Retrying),
+            count(FrameState::Failed),
+            count(FrameState::Waiting),
+        );
+        let block = Block::bordered().title(title);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        let [grid_area, detail_area] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(inner);
+
+        self.frame_columns = usize::from((grid_area.width / CELL_WIDTH).max(1));
+        self.frame_cursor = self.frame_cursor.min(states.len().saturating_sub(1));
+        // Keep the cursor's row on screen
+        let cursor_row = self.frame_cursor / self.frame_columns;
+        let first_row = cursor_row.saturating_sub(usize::from(grid_area.height).saturating_sub(1));
+        let lines: Vec<Line> = states
+            .chunks(self.frame_columns)
+            .enumerate()
+            .skip(first_row)
+            .map(|(row, states)| {
+                let spans = states.iter().enumerate().flat_map(|(column, state)| {
+                    let index = row * self.frame_columns + column;
+                    let mut style = Style::default().fg(Color::Black).bg(frame_color(*state));
+                    if index == self.frame_cursor {
+                        style = style.add_modifier(Modifier::REVERSED | Modifier::BOLD);
+                    }
+                    let width = usize::from(CELL_WIDTH) - 1;
+                    [
+                        Span::styled(format!("{index:>width$}"), style),
+                        Span::raw(" "),
+                    ]
+                });
+                Line::from(spans.collect::<Vec<_>>())
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), grid_area);
+
+        let detail = match states.get(self.frame_cursor) {
+            Some(state) => {
+                let index = self.frame_cursor as u32;
+                let attempts = frame_pods(&self.items, &name, index).count();
+                match frame_pods(&self.items, &name, index).next() {
+                    Some(pod) => format!(
+                        "Frame {index}: {} - {} on {}, {attempts} attempt(s) still around",
+                        frame_label(*state),
+                        pod.name,
+                        pod.node
+                    ),
+                    None => format!("Frame {index}: {}", frame_label(*state)),
+                }
+            }
+            None => "This job has no completions".to_string(),
+        };
+        frame.render_widget(
+            Paragraph::new(detail).style(Style::default().fg(Color::DarkGray)),
+            detail_area,
+        );
+    }
+
+    /// Job shown in the frame grid
+    fn frames_job(&self) -> Option<&JobData> {
+        let name = self.job_filter.as_ref()?;
+        self.jobs.iter().find(|job| &job.name == name)
+    }
+
+    /// Open the frame grid of the job under the cursor in the jobs table
+    pub fn open_frames(&mut self) {
+        let Some(name) = self.selected_job().map(|job| job.name.clone()) else {
+            return;
+        };
+        match self.jobs.iter().find(|job| job.name == name) {
+            Some(job) if job.indexed => {
+                self.job_filter = Some(name);
+                self.update_visible_pods();
+                self.view = View::Frames;
+                self.frame_cursor = 0;
+            }
+            _ => self.notify(
+                Level::Error,
+                format!("{name} is not an Indexed Job, it has no frames"),
+            ),
+        }
+    }
+
+    /// Move the frame cursor by a number of cells, clamped to the grid
+    pub fn move_frame_cursor(&mut self, cells: isize) {
+        let len = self
+            .frames_job()
+            .map(|job| job.completions.unwrap_or_default().max(0) as usize)
+            .unwrap_or_default();
+        self.frame_cursor = self
+            .frame_cursor
+            .saturating_add_signed(cells)
+            .min(len.saturating_sub(1));
+    }
+
+    /// Move the frame cursor a whole grid row up or down
+    pub fn move_frame_row(&mut self, down: bool) {
+        let columns = self.frame_columns as isize;
+        let cells = if down { columns } else { -columns };
+        // Stay put rather than clamp onto a different column
+        if self.frame_cursor as isize + cells >= 0 {
+            let before = self.frame_cursor;
+            self.move_frame_cursor(cells);
+            if self.frame_cursor.abs_diff(before) != self.frame_columns {
+                self.frame_cursor = before;
+            }
+        }
+    }
+
+    /// Stream the logs of the latest attempt at the frame under the cursor
+    pub fn open_frame_logs(&mut self) {
+        if self.selected_frame_pod().is_some() {
+            self.start_log_mode();
+        } else {
+            let frame = self.frame_cursor;
+            self.notify(Level::Info, format!("Frame {frame} has no pod (yet)"));
+        }
+    }
+
+    /// Ask to re-run the failed frames of the selected job
+    pub fn retry_key(&mut self) {
+        let Some(Some(name)) = self.selected_controller() else {
+            return;
+        };
+        let Some(job) = self.jobs.iter().find(|job| job.name == name) else {
+            self.notify(Level::Error, format!("Job {name} is gone"));
+            return;
+        };
+        if !job.indexed {
+            self.notify(
+                Level::Error,
+                format!("{name} is not an Indexed Job, it has no frames"),
+            );
+            return;
+        }
+        let mut frames = failed_frames(job, &self.items);
+        if frames.is_empty() {
+            self.notify(Level::Info, format!("{name} has no failed frames"));
+            return;
+        }
+        // Every frame becomes a Job of its own, so keep to the pods the job and its artist may
+        // run at once
+        let cap = job
+            .artist
+            .as_deref()
+            .and_then(|artist| self.config.parallelism_cap(artist));
+        let limit = [job.parallelism, cap].into_iter().flatten().min();
+        let keep = limit.map_or(frames.len(), |limit| {
+            usize::try_from(limit.max(1)).unwrap_or(1)
+        });
+        let left = frames.len().saturating_sub(keep);
+        frames.truncate(keep);
+        self.pending_confirmation = Some(ConfirmAction::RetryFrames {
+            job: name,
+            frames,
+            left,
+        });
+        self.confirmation_popup = true;
+    }
+
+    pub fn run_retry_frames(&mut self, job: String, frames: Vec<u32>) {
+        self.notify(
+            Level::Info,
+            format!("Re-running {} frame(s) of {job}...", frames.len()),
+        );
+        let client = self.client.clone();
+        let namespace = self.config.namespace.clone();
+        let tx = self.tx.clone();
+        let span = tracing::info_span!("retry_frames_action", %job);
+        tokio::spawn(
+            async move {
+                let (created, result) = retry_frames(client, &namespace, &job, &frames).await;
+                let result = result.map_err(|e| describe(&e));
+                let _ = tx.send(Message::RetryFrames {
+                    job,
+                    created,
+                    result,
+                });
+            }
+            .instrument(span),
+        );
+    }
+
+    /// Pod of the frame under the cursor, its latest attempt
+    pub fn selected_frame_pod(&self) -> Option<&Data> {
+        let job = self.job_filter.as_ref()?;
+        frame_pods(&self.items, job, self.frame_cursor as u32).next()
+    }
+}
+
+/// Pods that ran a frame, newest attempt first
+fn frame_pods<'a>(pods: &'a [Data], job: &'a str, index: u32) -> impl Iterator<Item = &'a Data> {
+    pods.iter()
+        .filter(move |p| p.controller.as_deref() == Some(job) && p.completion_index == Some(index))
+}
+
+/// Frame state to cell colors for the grid
+fn frame_color(state: FrameState) -> Color {
+    match state {
+        FrameState::Waiting => Color::DarkGray,
+        FrameState::Running => Color::Blue,
+        FrameState::Done => Color::Green,
+        FrameState::Failed => Color::Red,
+        FrameState::Retrying => Color::Yellow,
+    }
+}
+
+fn frame_label(state: FrameState) -> &'static str {
+    match state {
+        FrameState::Waiting => "Waiting",
+        FrameState::Running => "Running",
+        FrameState::Done => "Done",
+        FrameState::Failed => "Failed",
+        FrameState::Retrying => "Retrying",
+    }
+}
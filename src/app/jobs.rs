@@ -0,0 +1,192 @@
+use super::confirmation::ConfirmAction;
+use super::notifications::Level;
+use super::table::{format_age, format_run_time};
+use super::{App, Message, View};
+use crate::data::{JobData, JobSummary};
+use crate::k8s::{describe, suspend_jobs};
+
+use k8s_openapi::chrono::Utc;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Cell, Row, Table, TableState},
+};
+use tracing::Instrument;
+
+impl App {
+    /// Jobs table, one row per job with its pods aggregated
+    pub fn draw_job_table(&mut self, frame: &mut Frame, area: Rect) {
+        let rows = self.job_summaries().into_iter().map(|job| {
+            let age = job
+                .created_at
+                .as_ref()
+                .map(format_age)
+                .unwrap_or_else(|| "n/a".into());
+            let run_time = job
+                .started_at
+                .as_ref()
+                .map(|s| format_run_time(s, &job.finished_at.unwrap_or_else(Utc::now)))
+                .unwrap_or_else(|| "n/a".into());
+            let style = job_state_colors(&job.state);
+            let artist_style = self.artist_style(&job.artist);
+            Row::new(vec![
+                Cell::from(job.name.clone()),
+                Cell::from(job.state.clone()),
+                Cell::from(job.artist.clone()).style(artist_style),
+                Cell::from(format!(
+                    "{}/{}/{}/{}",
+                    job.running, job.succeeded, job.failed, job.pending
+                )),
+                // Running pods against the requested parallelism
+                Cell::from(match job.parallelism {
+                    Some(parallelism) => format!("{}/{parallelism}", job.running),
+                    None => job.running.to_string(),
+                }),
+                Cell::from(job.total.to_string()),
+                Cell::from(run_time),
+                Cell::from(age),
+            ])
+            .style(style)
+        });
+        let columns = [
+            ("Job", Constraint::Percentage(35)),
+            ("State", Constraint::Percentage(10)),
+            ("Artist", Constraint::Percentage(10)),
+            ("Run/Done/Fail/Pend", Constraint::Percentage(15)),
+            ("Parallel", Constraint::Percentage(5)),
+            ("Pods", Constraint::Percentage(5)),
+            ("Run Time", Constraint::Percentage(10)),
+            ("Age", Constraint::Percentage(10)),
+        ];
+        let table = Table::new(rows, columns.iter().map(|(_, c)| *c))
+            .header(Row::new(
+                columns.iter().map(|(title, _)| Cell::from(*title)),
+            ))
+            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("⇝")
+            .block(Block::bordered().title(format!("{} jobs", self.scope_label())));
+        frame.render_stateful_widget(table, area, &mut self.job_state);
+    }
+
+    /// Rows of the jobs table
+    pub fn job_summaries(&self) -> Vec<&JobSummary> {
+        self.summaries
+            .iter()
+            .filter(|job| self.in_scope(&job.artist))
+            .collect()
+    }
+
+    /// Job under the cursor in the jobs table
+    pub fn selected_job(&self) -> Option<&JobSummary> {
+        let i = self.job_state.selected()?;
+        self.job_summaries().into_iter().nth(i)
+    }
+
+    /// Drill down from a job into the pod table, showing only that job's pods
+    pub fn open_job(&mut self) {
+        if let Some(job) = self.selected_job() {
+            self.job_filter = Some(job.name.clone());
+            self.update_visible_pods();
+            self.view = View::Pods;
+            self.state = TableState::default().with_selected(0);
+        }
+    }
+
+    /// Back from a job's pods to the jobs table
+    pub fn close_job(&mut self) {
+        self.job_filter = None;
+        self.update_visible_pods();
+        self.view = View::Jobs;
+        self.state = TableState::default().with_selected(0);
+    }
+
+    /// Jobs making up a controller: a Job and the Jobs retrying its frames, or every Job an owner
+    /// such as a CronJob or a PDG graph object owns. What cancelling, suspending and setting the
+    /// parallelism of a controller act on.
+    pub fn controller_jobs(&self, controller: &str) -> Vec<&JobData> {
+        self.jobs
+            .iter()
+            .filter(|job| job.controllers.iter().any(|c| c == controller))
+            .collect()
+    }
+
+    /// Names of [`App::controller_jobs`], for the API calls acting on them
+    pub fn controller_job_names(&self, controller: &str) -> Vec<String> {
+        self.controller_jobs(controller)
+            .into_iter()
+            .map(|job| job.name.clone())
+            .collect()
+    }
+
+    /// Ask to suspend the controllers of the marked pods or the selected row, or resume them
+    /// when they're all suspended
+    pub fn suspend_key(&mut self) {
+        let Some(controllers) = self.target_controllers() else {
+            return;
+        };
+        // Going by the same Jobs that get patched, of which finished ones can't be resumed
+        let suspended = controllers.iter().all(|controller| {
+            let unfinished: Vec<&JobData> = self
+                .controller_jobs(controller)
+                .into_iter()
+                .filter(|job| job.finished_state.is_none())
+                .collect();
+            !unfinished.is_empty() && unfinished.iter().all(|job| job.suspended)
+        });
+        self.pending_confirmation = Some(if suspended {
+            ConfirmAction::ResumeJobs { controllers }
+        } else {
+            ConfirmAction::SuspendJobs { controllers }
+        });
+        self.confirmation_popup = true;
+    }
+
+    pub fn run_suspend_jobs(&mut self, controllers: Vec<String>, suspend: bool) {
+        let verb = if suspend { "Suspending" } else { "Resuming" };
+        self.notify(
+            Level::Info,
+            format!("{verb} jobs for {}...", controllers.join(", ")),
+        );
+        let client = self.client.clone();
+        let namespace = self.config.namespace.clone();
+        let field_manager = self.config.field_manager.clone();
+        let tx = self.tx.clone();
+        let targets: Vec<(String, Vec<String>)> = controllers
+            .into_iter()
+            .map(|controller| {
+                let names = self.controller_job_names(&controller);
+                (controller, names)
+            })
+            .collect();
+        let span = tracing::info_span!("suspend_jobs_action", ?targets, suspend);
+        tokio::spawn(
+            async move {
+                for (controller, names) in targets {
+                    let result =
+                        suspend_jobs(client.clone(), &namespace, &names, &field_manager, suspend)
+                            .await
+                            .map_err(|e| describe(&e));
+                    let _ = tx.send(Message::SuspendJobs {
+                        controller,
+                        suspend,
+                        result,
+                    });
+                }
+            }
+            .instrument(span),
+        );
+    }
+}
+
+/// Job state to colors for the jobs table
+fn job_state_colors(state: &str) -> Style {
+    match state {
+        "Running" => Style::default().fg(Color::Green),
+        "Pending" | "Waiting" => Style::default().fg(Color::Blue),
+        "Suspended" => Style::default().fg(Color::Yellow),
+        "Complete" => Style::default().fg(Color::DarkGray),
+        "Failed" => Style::default().fg(Color::Red),
+        _ => Style::default(),
+    }
+}
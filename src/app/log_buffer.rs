@@ -0,0 +1,331 @@
+use super::ansi;
+use super::severity::{Patterns, Severity};
+
+use ratatui::style::Style;
+use std::collections::VecDeque;
+use std::ops::Range;
+use unicode_width::UnicodeWidthChar;
+
+/// The most recent lines of a log stream. Lines are numbered from the start of the stream, so a
+/// number keeps pointing at the same line as older ones are dropped.
+pub struct LogBuffer {
+    lines: VecDeque<LogLine>,
+    capacity: usize,
+    /// Number of the oldest line still kept
+    first: u64,
+}
+
+/// A log line as shown, without the escape codes it came with
+pub struct LogLine {
+    pub text: String,
+    /// Styles the line's escape codes set, each from its byte offset in `text` on
+    pub styles: Vec<(usize, Style)>,
+    pub severity: Option<Severity>,
+}
+
+impl LogLine {
+    pub fn new(raw: &str, patterns: &Patterns) -> Self {
+        let (text, styles) = ansi::parse(raw);
+        let severity = patterns.classify(&text);
+        Self {
+            text,
+            styles,
+            severity,
+        }
+    }
+}
+
+/// A row of wrapped log text: the line and which of its wrapped rows
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LogPos {
+    pub line: u64,
+    pub row: usize,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            capacity: capacity.max(1),
+            first: 0,
+        }
+    }
+
+    /// Append a line, dropping the oldest one when full
+    pub fn push(&mut self, line: LogLine) {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+            self.first += 1;
+        }
+        self.lines.push_back(line);
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.first = 0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Number of the oldest line kept
+    pub fn first(&self) -> u64 {
+        self.first
+    }
+
+    /// Number the next line will get
+    pub fn end(&self) -> u64 {
+        self.first + self.lines.len() as u64
+    }
+
+    pub fn get(&self, line: u64) -> Option<&LogLine> {
+        let index = usize::try_from(line.checked_sub(self.first)?).ok()?;
+        self.lines.get(index)
+    }
+
+    /// Line numbers and lines, oldest first
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (u64, &str)> {
+        self.lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| (self.first + i as u64, line.text.as_str()))
+    }
+}
+
+/// The lines a log view scrolls through: every line kept, or only some of them
+pub struct LogLines<'a> {
+    logs: &'a LogBuffer,
+    /// Numbers of the lines shown, oldest first, or all of them when unset
+    only: Option<&'a VecDeque<u64>>,
+    /// Cells long lines wrap at
+    width: usize,
+}
+
+impl<'a> LogLines<'a> {
+    pub fn new(logs: &'a LogBuffer, only: Option<&'a VecDeque<u64>>, width: usize) -> Self {
+        Self { logs, only, width }
+    }
+
+    fn first(&self) -> Option<u64> {
+        match self.only {
+            Some(only) => only.front().copied(),
+            None => (!self.logs.is_empty()).then_some(self.logs.first()),
+        }
+    }
+
+    fn last(&self) -> Option<u64> {
+        match self.only {
+            Some(only) => only.back().copied(),
+            None => self.logs.end().checked_sub(1),
+        }
+    }
+
+    /// First shown line at or after `line`
+    fn at_or_after(&self, line: u64) -> Option<u64> {
+        match self.only {
+            Some(only) => only.get(only.partition_point(|&n| n < line)).copied(),
+            None => (line < self.logs.end()).then(|| line.max(self.logs.first())),
+        }
+    }
+
+    /// Last shown line before `line`
+    fn before(&self, line: u64) -> Option<u64> {
+        match self.only {
+            Some(only) => only
+                .partition_point(|&n| n < line)
+                .checked_sub(1)
+                .and_then(|i| only.get(i).copied()),
+            None => (line > self.logs.first()).then(|| line - 1),
+        }
+    }
+
+    fn rows(&self, line: u64) -> usize {
+        let text = self.logs.get(line).map_or("", |line| line.text.as_str());
+        wrapped_rows(text, self.width)
+    }
+
+    /// Shown lines from `line` on, with their numbers
+    pub fn from(&self, line: u64) -> impl Iterator<Item = (u64, &'a LogLine)> + '_ {
+        std::iter::successors(self.at_or_after(line), move |&n| self.at_or_after(n + 1))
+            .filter_map(|n| Some((n, self.logs.get(n)?)))
+    }
+
+    /// Position of the top row when the last row of the last line is at the bottom of a
+    /// viewport `height` rows high, wrapping only the lines that end up in view
+    pub fn tail_top(&self, height: usize) -> LogPos {
+        let mut remaining = height.max(1);
+        let mut line = self.last();
+        while let Some(n) = line {
+            let rows = self.rows(n);
+            if rows >= remaining {
+                return LogPos {
+                    line: n,
+                    row: rows - remaining,
+                };
+            }
+            remaining -= rows;
+            line = self.before(n);
+        }
+        LogPos {
+            line: self.first().unwrap_or_default(),
+            row: 0,
+        }
+    }
+
+    /// Keep a position on a line that's still shown, moving to the next one that is
+    pub fn clamp(&self, pos: LogPos) -> LogPos {
+        match self.at_or_after(pos.line) {
+            Some(line) if line == pos.line => LogPos {
+                line,
+                row: pos.row.min(self.rows(line) - 1),
+            },
+            Some(line) => LogPos { line, row: 0 },
+            None => LogPos {
+                line: self.last().unwrap_or_default(),
+                row: 0,
+            },
+        }
+    }
+
+    /// Move a position by a number of wrapped rows, stopping at either end
+    pub fn step(&self, mut pos: LogPos, rows: isize) -> LogPos {
+        if rows < 0 {
+            for _ in 0..rows.unsigned_abs() {
+                if pos.row > 0 {
+                    pos.row -= 1;
+                } else if let Some(line) = self.before(pos.line) {
+                    pos = LogPos {
+                        line,
+                        row: self.rows(line) - 1,
+                    };
+                } else {
+                    break;
+                }
+            }
+        } else {
+            for _ in 0..rows {
+                if pos.row + 1 < self.rows(pos.line) {
+                    pos.row += 1;
+                } else if let Some(line) = self.at_or_after(pos.line + 1) {
+                    pos = LogPos { line, row: 0 };
+                } else {
+                    break;
+                }
+            }
+        }
+        pos
+    }
+
+    /// Top of the view when jumping to a line, a third of the way down so it has some context
+    pub fn jump_top(&self, line: u64, height: usize) -> LogPos {
+        let pos = LogPos { line, row: 0 };
+        self.step(pos, -((height / 3) as isize))
+    }
+}
+
+/// How many rows a line takes up when wrapped at `width` cells
+pub fn wrapped_rows(line: &str, width: usize) -> usize {
+    wrap(line, width).count()
+}
+
+/// Split a line into byte ranges of at most `width` cells, breaking anywhere. An empty line is
+/// one empty row.
+pub fn wrap(line: &str, width: usize) -> impl Iterator<Item = Range<usize>> {
+    let width = width.max(1);
+    let mut start = 0;
+    let mut first = true;
+    std::iter::from_fn(move || {
+        if start == line.len() && !first {
+            return None;
+        }
+        first = false;
+        let rest = &line[start..];
+        let mut used = 0;
+        let mut end = rest.len();
+        for (i, c) in rest.char_indices() {
+            let char_width = c.width().unwrap_or_default();
+            // Always take at least one character, even one wider than the viewport
+            if used + char_width > width && i > 0 {
+                end = i;
+                break;
+            }
+            used += char_width;
+        }
+        let row = start..start + end;
+        start += end;
+        Some(row)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::severity::Classifier;
+    use std::collections::BTreeMap;
+
+    /// Buffer holding `lines`, after `dropped` older lines were evicted
+    fn buffer(lines: &[&str], dropped: usize) -> LogBuffer {
+        let classifier = Classifier::new(&BTreeMap::new()).unwrap();
+        let mut logs = LogBuffer::new(lines.len());
+        for line in std::iter::repeat_n("old", dropped).chain(lines.iter().copied()) {
+            logs.push(LogLine::new(line, classifier.patterns(None)));
+        }
+        logs
+    }
+
+    fn pos(line: u64, row: usize) -> LogPos {
+        LogPos { line, row }
+    }
+
+    #[test]
+    fn full_buffer_drops_the_oldest_line_and_keeps_numbers() {
+        let logs = buffer(&["a", "b", "c"], 2);
+        assert_eq!((logs.first(), logs.end()), (2, 5));
+        assert!(logs.get(1).is_none());
+        assert_eq!(logs.get(2).map(|line| line.text.as_str()), Some("a"));
+        let numbered: Vec<(u64, &str)> = logs.iter().collect();
+        assert_eq!(numbered, [(2, "a"), (3, "b"), (4, "c")]);
+    }
+
+    #[test]
+    fn wrap_breaks_at_cell_width() {
+        fn rows(line: &str, width: usize) -> Vec<&str> {
+            wrap(line, width).map(|range| &line[range]).collect()
+        }
+        assert_eq!(rows("abcdefg", 3), ["abc", "def", "g"]);
+        assert_eq!(rows("", 3), [""]);
+        // Wide characters take two cells, and one always fits even when wider than the row
+        assert_eq!(rows("ショット", 5), ["ショ", "ット"]);
+        assert_eq!(rows("ショ", 1), ["シ", "ョ"]);
+    }
+
+    #[test]
+    fn tail_top_fills_the_viewport_from_the_bottom() {
+        // Line 1 wraps to three rows at width 2
+        let logs = buffer(&["a", "bbbbb", "c"], 0);
+        let lines = LogLines::new(&logs, None, 2);
+        assert_eq!(lines.tail_top(2), pos(1, 2));
+        assert_eq!(lines.tail_top(4), pos(1, 0));
+        assert_eq!(lines.tail_top(10), pos(0, 0));
+    }
+
+    #[test]
+    fn filtered_lines_skip_the_others() {
+        let logs = buffer(&["a", "bbbbb", "c", "d", "e"], 10);
+        // Lines 10 to 14 are kept, of which 11, 13 and 14 are shown
+        let only = VecDeque::from([11, 13, 14]);
+        let lines = LogLines::new(&logs, Some(&only), 2);
+        let shown: Vec<u64> = lines.from(0).map(|(n, _)| n).collect();
+        assert_eq!(shown, [11, 13, 14]);
+        assert_eq!(lines.tail_top(3), pos(11, 2));
+        assert_eq!(lines.step(pos(11, 1), 2), pos(13, 0));
+        assert_eq!(lines.step(pos(13, 0), -1), pos(11, 2));
+        assert_eq!(lines.step(pos(14, 0), 5), pos(14, 0));
+        assert_eq!(lines.step(pos(11, 0), -5), pos(11, 0));
+        // A position on a hidden line moves to the next one shown, or the last
+        assert_eq!(lines.clamp(pos(12, 0)), pos(13, 0));
+        assert_eq!(lines.clamp(pos(11, 7)), pos(11, 2));
+        assert_eq!(lines.clamp(pos(20, 0)), pos(14, 0));
+    }
+}
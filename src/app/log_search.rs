@@ -0,0 +1,274 @@
+use super::App;
+use super::log_buffer::{LogBuffer, LogLines};
+use super::notifications::Level;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::style::{Color, Modifier, Style};
+use regex::Regex;
+use std::collections::VecDeque;
+use std::ops::Range;
+
+/// A regex searched for in the open logs, keeping track of the lines that match as they stream in
+pub struct LogSearch {
+    regex: Regex,
+    /// Searched with `?`, so `n` goes towards older lines
+    pub backward: bool,
+    /// Numbers of the kept lines that match, oldest first
+    pub matches: VecDeque<u64>,
+    /// Match last jumped to
+    pub current: Option<u64>,
+    /// Show only the matching lines, like grep
+    pub filter: bool,
+}
+
+/// Search being typed into the status line
+pub struct SearchInput {
+    pub input: String,
+    pub backward: bool,
+    /// Why the input isn't a valid regex
+    pub error: Option<String>,
+}
+
+impl SearchInput {
+    /// Shown in front of the input in the status line
+    pub fn prefix(&self) -> char {
+        if self.backward { '?' } else { '/' }
+    }
+}
+
+impl LogSearch {
+    fn new(regex: Regex, backward: bool, logs: &LogBuffer) -> Self {
+        let matches = logs
+            .iter()
+            .filter(|(_, line)| regex.is_match(line))
+            .map(|(number, _)| number)
+            .collect();
+        Self {
+            regex,
+            backward,
+            matches,
+            current: None,
+            filter: false,
+        }
+    }
+
+    pub fn pattern(&self) -> &str {
+        self.regex.as_str()
+    }
+
+    /// Look at a line that just arrived, forgetting matches the buffer has since dropped
+    pub fn push(&mut self, number: u64, line: &str, first: u64) {
+        while self.matches.front().is_some_and(|&n| n < first) {
+            self.matches.pop_front();
+        }
+        if self.regex.is_match(line) {
+            self.matches.push_back(number);
+        }
+    }
+
+    /// Nearest match after `line`, or before it going backward, wrapping around at the ends
+    fn next_match(&self, line: u64, backward: bool) -> Option<u64> {
+        let split = if backward {
+            self.matches.partition_point(|&n| n < line)
+        } else {
+            self.matches.partition_point(|&n| n <= line)
+        };
+        self.wrap_around(split, backward)
+    }
+
+    /// Nearest match at `line` or after it, or at or before it going backward, wrapping around
+    /// at the ends. Where a new search first jumps to.
+    fn first_match(&self, line: u64, backward: bool) -> Option<u64> {
+        let split = if backward {
+            self.matches.partition_point(|&n| n <= line)
+        } else {
+            self.matches.partition_point(|&n| n < line)
+        };
+        self.wrap_around(split, backward)
+    }
+
+    /// The match at `split` going forward, or the one before it going backward, and the one at
+    /// the other end when there is none
+    fn wrap_around(&self, split: usize, backward: bool) -> Option<u64> {
+        if backward {
+            split
+                .checked_sub(1)
+                .and_then(|i| self.matches.get(i))
+                .or(self.matches.back())
+                .copied()
+        } else {
+            self.matches.get(split).or(self.matches.front()).copied()
+        }
+    }
+
+    /// Title text: the pattern, and which match of how many is current
+    pub fn counter(&self) -> String {
+        let prefix = if self.backward { '?' } else { '/' };
+        let total = self.matches.len();
+        let current = self
+            .current
+            .and_then(|line| self.matches.binary_search(&line).ok())
+            .map_or("-".to_string(), |i| (i + 1).to_string());
+        let filter = if self.filter {
+            ", only matching lines"
+        } else {
+            ""
+        };
+        format!("{prefix}{} {current}/{total}{filter}", self.pattern())
+    }
+
+    /// Byte ranges of the parts of a line that match
+    pub fn find_iter<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+        self.regex.find_iter(line).map(|found| found.range())
+    }
+
+    /// Highlight of the matches, the current match's more so
+    pub fn match_style(current: bool) -> Style {
+        if current {
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Black).bg(Color::DarkGray)
+        }
+    }
+}
+
+impl App {
+    /// Start typing a search of the logs, `/` forward or `?` backward
+    pub fn search_key(&mut self, backward: bool) {
+        self.log_search_input = Some(SearchInput {
+            input: String::new(),
+            backward,
+            error: None,
+        });
+    }
+
+    /// Keybinds while typing a search
+    pub fn handle_search_key(&mut self, key: KeyEvent) {
+        let Some(search) = self.log_search_input.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => self.log_search_input = None,
+            KeyCode::Enter if search.input.is_empty() => self.log_search_input = None,
+            KeyCode::Enter => match Regex::new(&search.input) {
+                Ok(regex) => {
+                    let backward = search.backward;
+                    self.log_search_input = None;
+                    let search = LogSearch::new(regex, backward, &self.logs);
+                    if search.matches.is_empty() {
+                        self.notify(Level::Info, format!("No lines match {}", search.pattern()));
+                    }
+                    self.log_search = Some(search);
+                    self.next_match(false);
+                }
+                Err(e) => {
+                    search.error = Some(e.to_string().lines().last().unwrap_or_default().into())
+                }
+            },
+            KeyCode::Backspace => {
+                search.input.pop();
+                search.error = None;
+            }
+            KeyCode::Char(c) => {
+                search.input.push(c);
+                search.error = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Jump to the next match in the direction of the search, or the other way round
+    pub fn next_match(&mut self, reverse: bool) {
+        let Some(search) = self.log_search.as_ref() else {
+            self.notify(Level::Info, "Search with / or ? first");
+            return;
+        };
+        let backward = search.backward != reverse;
+        let height = usize::from(self.log_viewport.height);
+        // From the match last jumped to, or else what's in view, the tail while following
+        let found = match (search.current, self.log_top) {
+            (Some(line), _) => search.next_match(line, backward),
+            (None, Some(top)) => search.first_match(top.line, backward),
+            (None, None) if backward => search.first_match(self.logs.end(), backward),
+            (None, None) => search.first_match(self.log_lines().tail_top(height).line, backward),
+        };
+        let Some(line) = found else {
+            return;
+        };
+        let top = self.log_lines().jump_top(line, height);
+        if let Some(search) = self.log_search.as_mut() {
+            search.current = Some(line);
+        }
+        self.log_top = Some(top);
+    }
+
+    /// Show only the lines matching the search, or every line again
+    pub fn toggle_log_filter(&mut self) {
+        let Some(search) = self.log_search.as_mut() else {
+            self.notify(Level::Info, "Search with / or ? first");
+            return;
+        };
+        search.filter = !search.filter;
+        let current = search.current;
+        let height = usize::from(self.log_viewport.height);
+        // Stay on the current match, if any
+        self.log_top = current.map(|line| self.log_lines().jump_top(line, height));
+    }
+
+    /// Lines the log view scrolls through, only matching ones while filtering
+    pub fn log_lines(&self) -> LogLines<'_> {
+        let only = self
+            .log_search
+            .as_ref()
+            .filter(|search| search.filter)
+            .map(|search| &search.matches);
+        LogLines::new(&self.logs, only, self.log_wrap_width())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::log_buffer::LogLine;
+    use crate::app::severity::Classifier;
+    use std::collections::BTreeMap;
+
+    /// Search for `x` in the given lines
+    fn search(lines: &[&str]) -> LogSearch {
+        let classifier = Classifier::new(&BTreeMap::new()).unwrap();
+        let mut logs = LogBuffer::new(lines.len());
+        for line in lines {
+            logs.push(LogLine::new(line, classifier.patterns(None)));
+        }
+        LogSearch::new(Regex::new("x").unwrap(), false, &logs)
+    }
+
+    #[test]
+    fn next_match_wraps_around_at_either_end() {
+        let search = search(&["x", "-", "x", "-", "x"]);
+        assert_eq!(search.next_match(0, false), Some(2));
+        assert_eq!(search.next_match(4, false), Some(0));
+        assert_eq!(search.next_match(4, true), Some(2));
+        assert_eq!(search.next_match(0, true), Some(4));
+    }
+
+    #[test]
+    fn first_match_can_land_on_the_line_it_starts_from() {
+        let search = search(&["x", "-", "x", "-"]);
+        assert_eq!(search.first_match(0, false), Some(0));
+        assert_eq!(search.first_match(1, false), Some(2));
+        assert_eq!(search.first_match(3, false), Some(0));
+        assert_eq!(search.first_match(2, true), Some(2));
+        assert_eq!(search.first_match(1, true), Some(0));
+    }
+
+    #[test]
+    fn no_matches_finds_nothing() {
+        let search = search(&["-", "-"]);
+        assert_eq!(search.next_match(0, false), None);
+        assert_eq!(search.first_match(0, true), None);
+    }
+}
@@ -1,42 +1,104 @@
 use super::App;
 use super::Mode;
-use crate::k8s::stream_logs;
+use super::containers::default_container;
+use super::log_buffer::{LogLine, LogLines, LogPos, wrap};
+use super::log_search::LogSearch;
+use super::severity::Severity;
+use crate::k8s::{describe, stream_logs};
 use humantime::format_duration;
 use k8s_openapi::chrono::{DateTime, Utc};
 use ratatui::{
     Frame,
-    layout::{Constraint, Direction, Layout},
-    style::{Style, Stylize},
-    widgets::{Block, Borders, Gauge, Paragraph, Wrap},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Gauge, Paragraph},
 };
+use std::ops::Range;
 use std::time::Duration;
+use tracing::Instrument;
 impl App {
     /// Log view
-    pub fn draw_logs(&mut self, frame: &mut Frame, pod: &str, start: &DateTime<Utc>) {
-        let area = frame.area();
+    pub fn draw_logs(&mut self, frame: &mut Frame, area: Rect, pod: &str, start: &DateTime<Utc>) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([Constraint::Min(1), Constraint::Length(2)])
             .split(area);
-        let text = if self.logs.is_empty() {
-            "(no data yet)".to_string()
-        } else {
-            format!("Start Logs for {}\n{}", pod, self.logs.join("\n"))
-        };
+        if self.log_top.is_none() {
+            self.log_seen = self.logs.end();
+        }
+        let mut title = format!("Logs for {}{}", pod, self.log_source_label());
+        match self.log_top {
+            None => title.push_str(" - following"),
+            Some(_) => {
+                title.push_str(" - paused");
+                let new = self.logs.end().saturating_sub(self.log_seen);
+                if new > 0 {
+                    title.push_str(&format!(", {new} new line(s)"));
+                }
+            }
+        }
+        if let Some(search) = &self.log_search {
+            title.push_str(&format!(" [{}]", search.counter()));
+        }
+        if !self.log_wrap && self.log_hscroll > 0 {
+            title.push_str(&format!(", from column {}", self.log_hscroll + 1));
+        }
         let block = Block::default()
-            .title(format!("Logs for {}", pod))
+            .title(title)
+            .title_bottom(
+                "(PgUp/PgDn, Ctrl-u/d, g/G) scroll, (f) follow, (w) wrap, (h/l) sideways, (#) line numbers, (/ ?) search, (n/N) next/previous, (&) only matches, (c) container, (p) previous run",
+            )
             .borders(Borders::ALL);
-        let para = Paragraph::new(text.clone())
-            .block(block)
-            .wrap(Wrap { trim: false });
-        let total_lines = para.line_count(chunks[0].width) as u16;
-        let mut scroll_y = total_lines.saturating_sub(chunks[0].height);
-        self.max_log_lines = scroll_y;
-        scroll_y = scroll_y.saturating_sub(self.scroll_offset);
-        frame.render_widget(para.scroll((scroll_y, 0)), chunks[0]);
-        if let Some(pct) = latest_alf_progress(&self.logs) {
+        let inner = block.inner(chunks[0]);
+        frame.render_widget(block, chunks[0]);
+        // Room for the widest line number still to come
+        let gutter = if self.log_gutter {
+            self.logs.end().max(1).to_string().len() as u16 + 1
+        } else {
+            0
+        };
+        let [gutter_area, text_area] =
+            Layout::horizontal([Constraint::Length(gutter), Constraint::Min(1)]).areas(inner);
+        self.log_viewport = text_area;
+        if self.logs.is_empty() {
+            frame.render_widget(Paragraph::new("(no data yet)"), text_area);
+        } else {
+            let height = usize::from(text_area.height);
+            let lines = self.log_lines();
+            // Lines arriving or being dropped from the buffer don't move a scrolled view
+            let top = match self.log_top {
+                Some(pos) => lines.clamp(pos),
+                None => lines.tail_top(height),
+            };
+            let (numbers, rows) = visible_rows(
+                &lines,
+                self.log_search.as_ref(),
+                top,
+                self.log_wrap_width(),
+                height,
+            );
+            let numbers = numbers.into_iter().map(|number| {
+                let text = number.map_or(String::new(), |n| n.to_string());
+                Line::from(Span::styled(
+                    format!("{text:>width$}", width = usize::from(gutter - 1)),
+                    Style::default().fg(Color::DarkGray),
+                ))
+            });
+            if gutter > 0 {
+                frame.render_widget(Paragraph::new(numbers.collect::<Vec<_>>()), gutter_area);
+            }
+            frame.render_widget(
+                Paragraph::new(rows).scroll((0, self.log_hscroll)),
+                text_area,
+            );
+            if self.log_top.is_some() {
+                self.log_top = Some(top);
+            }
+        }
+        if let Some(pct) = self.log_progress {
             let elapsed = Utc::now().signed_duration_since(*start).num_seconds();
-            let seconds_left = if pct >= 100 || pct < 1 {
+            let seconds_left = if !(1..100).contains(&pct) {
                 0.0
             } else {
                 (elapsed as f64 / ((pct as f64) / 100.0)) - elapsed as f64
@@ -48,28 +110,111 @@ impl App {
                 .percent(pct);
             frame.render_widget(gague, chunks[1]);
         }
+        self.draw_container_picker(frame);
+    }
+    /// Scroll the logs by a number of wrapped rows, down when positive. Scrolling to the end
+    /// follows new lines again.
+    pub fn scroll_logs(&mut self, rows: isize) {
+        let height = usize::from(self.log_viewport.height);
+        let lines = self.log_lines();
+        let tail = lines.tail_top(height);
+        let top = self.log_top.map_or(tail, |pos| lines.clamp(pos));
+        let top = lines.step(top, rows);
+        self.log_top = (top < tail).then_some(top);
+    }
+    /// Scroll the logs by a number of pages, down when positive
+    pub fn page_logs(&mut self, pages: f32) {
+        let rows = (f32::from(self.log_viewport.height.max(1)) * pages) as isize;
+        self.scroll_logs(rows);
+    }
+    /// Jump to the oldest line kept
+    pub fn logs_top(&mut self) {
+        self.log_top = Some(LogPos {
+            line: self.logs.first(),
+            row: 0,
+        });
+    }
+    /// Jump to the end of the logs and follow new lines
+    pub fn logs_bottom(&mut self) {
+        self.log_top = None;
+    }
+    /// Stop following new lines where the view is, or follow them again
+    pub fn toggle_follow(&mut self) {
+        self.log_top = match self.log_top {
+            Some(_) => None,
+            None => {
+                let height = usize::from(self.log_viewport.height);
+                Some(self.log_lines().tail_top(height))
+            }
+        };
+    }
+    /// Switch between wrapping long lines and scrolling sideways
+    pub fn toggle_wrap(&mut self) {
+        self.log_wrap = !self.log_wrap;
+        self.log_hscroll = 0;
+        if let Some(pos) = self.log_top.as_mut() {
+            pos.row = 0;
+        }
+    }
+    /// Scroll long lines sideways by a number of columns, right when positive. Only while not
+    /// wrapping.
+    pub fn hscroll_logs(&mut self, columns: i16) {
+        if !self.log_wrap {
+            self.log_hscroll = self.log_hscroll.saturating_add_signed(columns);
+        }
     }
-    /// Clamping scroll for logs
-    pub fn scroll_logs(&mut self, down: bool) {
-        if down {
-            self.scroll_offset = self.scroll_offset.saturating_sub(1);
+    /// Width log lines are wrapped at, or as good as never while wrapping is off
+    pub fn log_wrap_width(&self) -> usize {
+        if self.log_wrap {
+            usize::from(self.log_viewport.width)
         } else {
-            self.scroll_offset = self
-                .scroll_offset
-                .saturating_add(1)
-                .clamp(0, self.max_log_lines);
+            usize::MAX
         }
     }
-    /// Spawn async log stream
+    /// Open the logs of the selected pod's default container
     pub fn start_log_mode(&mut self) {
-        if let Some(idx) = self.state.selected().and_then(|i| self.items.get(i)) {
-            let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-            let pod = idx.name.clone();
-            self.log_rx = Some(rx);
-            // let rt_handle = &self.rt;
-            let client = self.client.clone();
-            self.log_task = Some(self.rt.spawn(async move {
-                match stream_logs(client, &pod).await {
+        if let Some(pod) = self.selected_pod() {
+            let mode = Mode::Logs {
+                pod: pod.name.clone(),
+                start: pod.started_at.unwrap_or_else(Utc::now),
+            };
+            let dcc = pod.labels.get(&self.config.dcc_label).cloned();
+            self.log_container = default_container(pod);
+            self.log_dcc = dcc;
+            self.log_previous = false;
+            self.mode = mode;
+            self.open_log_stream();
+        }
+    }
+    /// Spawn async log stream of the open pod, for the picked container and run
+    pub fn open_log_stream(&mut self) {
+        let Mode::Logs { pod, .. } = &self.mode else {
+            return;
+        };
+        let pod = pod.clone();
+        if let Some(handle) = self.log_task.take() {
+            handle.abort();
+        }
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        self.log_rx = Some(rx);
+        let client = self.client.clone();
+        let namespace = self.config.namespace.clone();
+        let tail_lines = self.config.log_tail_lines;
+        let container = self.log_container.clone();
+        let previous = self.log_previous;
+        let span = tracing::info_span!("log_stream_action", %pod, ?container, previous);
+        self.log_task = Some(tokio::spawn(
+            async move {
+                match stream_logs(
+                    client,
+                    &namespace,
+                    &pod,
+                    container.as_deref(),
+                    previous,
+                    tail_lines,
+                )
+                .await
+                {
                     Ok(reader) => {
                         use futures::AsyncBufReadExt;
                         use futures::StreamExt;
@@ -90,28 +235,50 @@ impl App {
                         }
                     }
                     Err(e) => {
-                        let _ = tx.send(format!("Log error: {e}"));
+                        let _ = tx.send(format!("Log error: {}", describe(&e)));
                     }
                 }
-            }));
-            self.logs.clear();
-            self.mode = Mode::Logs {
-                pod: idx.name.clone(),
-                start: idx.started_at.clone().unwrap_or_else(Utc::now),
-            };
-        }
+            }
+            .instrument(span),
+        ));
+        self.logs.clear();
+        self.log_top = None;
+        self.log_search = None;
+        self.log_progress = None;
     }
     /// Get logs from async task
     pub fn drain_logs(&mut self) {
-        if let Some(rx) = self.log_rx.as_mut() {
-            while let Ok(line) = rx.try_recv() {
-                self.logs.push(line);
-            }
+        while let Some(line) = self.log_rx.as_mut().and_then(|rx| rx.try_recv().ok()) {
+            self.push_log(line);
+        }
+    }
+    /// Add a line to the log buffer, and to the search matches when it matches. Keeps the
+    /// latest progress it reports, so drawing doesn't scan the buffer for it.
+    pub fn push_log(&mut self, line: String) {
+        let number = self.logs.end();
+        let patterns = self.classifier.patterns(self.log_dcc.as_deref());
+        self.logs.push(LogLine::new(&line, patterns));
+        let Some(line) = self.logs.get(number) else {
+            return;
+        };
+        if let Some(pct) = parse_alf_progress(&line.text) {
+            self.log_progress = Some(pct);
+        }
+        if let Some(search) = self.log_search.as_mut() {
+            search.push(number, &line.text, self.logs.first());
         }
     }
     pub fn exit_log_mode(&mut self) {
         self.logs.clear();
-        self.scroll_offset = 0;
+        self.log_top = None;
+        self.log_hscroll = 0;
+        self.log_search = None;
+        self.log_search_input = None;
+        self.container_picker = None;
+        self.log_container = None;
+        self.log_previous = false;
+        self.log_progress = None;
+        self.log_dcc = None;
         self.log_rx = None;
         if let Some(handle) = self.log_task.take() {
             handle.abort();
@@ -129,7 +296,83 @@ fn parse_alf_progress(line: &str) -> Option<u16> {
     let pct = pct_str.parse::<u16>().ok()?;
     Some(pct.clamp(0, 100))
 }
-/// Get latest ALF_PROGRESS from log lines
-pub fn latest_alf_progress(lines: &[String]) -> Option<u16> {
-    lines.iter().rev().find_map(|line| parse_alf_progress(line))
+/// Wrapped rows filling a viewport from `top`, only wrapping the lines that show. Each row comes
+/// with its 1-based line number when it's the first row of its line.
+fn visible_rows<'a>(
+    lines: &LogLines<'a>,
+    search: Option<&LogSearch>,
+    top: LogPos,
+    width: usize,
+    height: usize,
+) -> (Vec<Option<u64>>, Vec<Line<'a>>) {
+    lines
+        .from(top.line)
+        .flat_map(|(number, line)| {
+            let current = search.is_some_and(|search| search.current == Some(number));
+            wrap(&line.text, width)
+                .enumerate()
+                .map(move |(row, range)| {
+                    let text = styled_row(line, range, search, current);
+                    ((row == 0).then_some(number + 1), text)
+                })
+        })
+        .skip(top.row)
+        .take(height)
+        .unzip()
+}
+/// A wrapped row of a line, coloured by its severity under the colours it came with, and the
+/// parts matching the search highlighted over both
+fn styled_row<'a>(
+    line: &'a LogLine,
+    row: Range<usize>,
+    search: Option<&LogSearch>,
+    current: bool,
+) -> Line<'a> {
+    let base = line.severity.map_or(Style::default(), Severity::style);
+    let mut runs = Vec::new();
+    let (mut at, mut style) = (0, Style::default());
+    let ends = line.styles.iter().copied();
+    for (next, next_style) in ends.chain([(line.text.len(), Style::default())]) {
+        let (start, end) = (at.max(row.start), next.min(row.end));
+        if start < end {
+            runs.push((start..end, base.patch(style)));
+        }
+        (at, style) = (next, next_style);
+    }
+    if let Some(search) = search {
+        let highlight = LogSearch::match_style(current);
+        for found in search.find_iter(&line.text) {
+            runs = overlay(runs, found, highlight);
+        }
+    }
+    Line::from(
+        runs.into_iter()
+            .map(|(range, style)| Span::styled(&line.text[range], style))
+            .collect::<Vec<_>>(),
+    )
+}
+/// Patch a style onto the part of some runs of text that falls in `range`, splitting the runs
+/// it only partly covers
+fn overlay(
+    runs: Vec<(Range<usize>, Style)>,
+    range: Range<usize>,
+    patch: Style,
+) -> Vec<(Range<usize>, Style)> {
+    let mut out = Vec::with_capacity(runs.len() + 2);
+    for (run, style) in runs {
+        let (start, end) = (
+            range.start.clamp(run.start, run.end),
+            range.end.clamp(run.start, run.end),
+        );
+        for (part, style) in [
+            (run.start..start, style),
+            (start..end, style.patch(patch)),
+            (end..run.end, style),
+        ] {
+            if !part.is_empty() {
+                out.push((part, style));
+            }
+        }
+    }
+    out
 }
@@ -0,0 +1,215 @@
+use super::{App, Mode};
+
+use k8s_openapi::chrono::{DateTime, Local};
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListItem, ListState, Paragraph, Wrap},
+};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Most toasts shown at once, older ones are dropped from the stack (but kept in history)
+const MAX_TOASTS: usize = 4;
+/// Messages kept for the `:messages` view
+const MAX_HISTORY: usize = 500;
+const TOAST_WIDTH: u16 = 50;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Success,
+    Error,
+}
+
+impl Level {
+    fn color(self) -> Color {
+        match self {
+            Level::Info => Color::Blue,
+            Level::Success => Color::Green,
+            Level::Error => Color::Red,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Success => "ok",
+            Level::Error => "error",
+        }
+    }
+
+    /// Errors stay up longer so there's time to read them
+    fn ttl(self) -> Duration {
+        match self {
+            Level::Info | Level::Success => Duration::from_secs(4),
+            Level::Error => Duration::from_secs(10),
+        }
+    }
+}
+
+pub struct Notification {
+    pub level: Level,
+    pub text: String,
+    pub time: DateTime<Local>,
+    expires: Instant,
+}
+
+/// Status line, toast stack and message history
+#[derive(Default)]
+pub struct Notifications {
+    toasts: VecDeque<Notification>,
+    history: VecDeque<Notification>,
+}
+
+impl Notifications {
+    pub fn push(&mut self, level: Level, text: impl Into<String>) {
+        let text = text.into();
+        let time = Local::now();
+        let expires = Instant::now() + level.ttl();
+        self.toasts.push_back(Notification {
+            level,
+            text: text.clone(),
+            time,
+            expires,
+        });
+        while self.toasts.len() > MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+        self.history.push_back(Notification {
+            level,
+            text,
+            time,
+            expires,
+        });
+        while self.history.len() > MAX_HISTORY {
+            self.history.pop_front();
+        }
+    }
+
+    /// Drop toasts that have been shown for long enough
+    pub fn expire(&mut self) {
+        let now = Instant::now();
+        self.toasts.retain(|t| t.expires > now);
+    }
+
+    pub fn latest(&self) -> Option<&Notification> {
+        self.history.back()
+    }
+
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+}
+
+impl App {
+    /// Report the outcome of an action to the user
+    pub fn notify(&mut self, level: Level, text: impl Into<String>) {
+        self.notifications.push(level, text);
+    }
+
+    /// One line status bar at the bottom of every view
+    pub fn draw_status_line(&self, frame: &mut Frame, area: Rect) {
+        let line = match (&self.command, self.notifications.latest()) {
+            (Some(command), _) => Line::from(format!(":{command}")),
+            (None, _) if let Some(prompt) = &self.prompt => {
+                Line::from(format!("{}{}", prompt.label(), prompt.input))
+            }
+            (None, _) if let Some(search) = &self.log_search_input => {
+                let mut spans = vec![Span::raw(format!("{}{}", search.prefix(), search.input))];
+                if let Some(error) = &search.error {
+                    spans.push(Span::styled(
+                        format!("  {error}"),
+                        Style::default().fg(Color::Red),
+                    ));
+                }
+                Line::from(spans)
+            }
+            (None, _) if let Some(filter) = &self.filter_input => {
+                let mut spans = vec![Span::raw(format!("/{}", filter.input))];
+                if let Some(error) = &filter.error {
+                    spans.push(Span::styled(
+                        format!("  {error}"),
+                        Style::default().fg(Color::Red),
+                    ));
+                }
+                Line::from(spans)
+            }
+            (None, Some(latest)) => Line::from(vec![
+                Span::styled(
+                    format!("[{}] ", latest.time.format("%H:%M:%S")),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(
+                    latest.text.clone(),
+                    Style::default().fg(latest.level.color()),
+                ),
+            ]),
+            (None, None) => Line::from(Span::styled(
+                "(:) for commands, :messages for message history",
+                Style::default().fg(Color::DarkGray),
+            )),
+        };
+        frame.render_widget(Paragraph::new(line), area);
+    }
+
+    /// Stack of recent toasts in the top right corner
+    pub fn draw_toasts(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let width = TOAST_WIDTH.min(area.width);
+        let mut y = area.y + 1;
+        for toast in self.notifications.toasts.iter().rev() {
+            let inner_width = width.saturating_sub(2).max(1) as usize;
+            let height = (toast.text.chars().count().div_ceil(inner_width) as u16).max(1) + 2;
+            if y + height > area.bottom() {
+                break;
+            }
+            let rect = Rect::new(area.right().saturating_sub(width + 1), y, width, height);
+            let style = Style::default().fg(toast.level.color());
+            let widget = Paragraph::new(toast.text.as_str())
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::bordered()
+                        .title(toast.level.label())
+                        .border_style(style),
+                );
+            frame.render_widget(Clear, rect);
+            frame.render_widget(widget, rect);
+            y += height;
+        }
+    }
+
+    /// `:messages` history view
+    pub fn draw_messages(&mut self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .notifications
+            .history
+            .iter()
+            .map(|n| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{} ", n.time.format("%H:%M:%S")),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        format!("{:<6}", n.level.label()),
+                        Style::default().fg(n.level.color()),
+                    ),
+                    Span::raw(n.text.clone()),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::bordered().title("Messages - (q) to go back"))
+            .highlight_style(Style::default().fg(Color::Black).bg(Color::Gray));
+        frame.render_stateful_widget(list, area, &mut self.messages_state);
+    }
+
+    pub fn open_messages(&mut self) {
+        let last = self.notifications.history_len().checked_sub(1);
+        self.messages_state = ListState::default().with_selected(last);
+        self.mode = Mode::Messages;
+    }
+}
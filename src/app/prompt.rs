@@ -0,0 +1,140 @@
+use super::notifications::Level;
+use super::{App, Message};
+use crate::k8s::{describe, set_parallelism};
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use tracing::Instrument;
+
+/// A value being typed into the status line for an action
+pub struct Prompt {
+    pub action: PromptAction,
+    pub input: String,
+}
+
+pub enum PromptAction {
+    Parallelism {
+        controller: String,
+        artist: String,
+        current: Option<i32>,
+    },
+}
+
+impl Prompt {
+    /// Shown in front of the input in the status line
+    pub fn label(&self) -> String {
+        match &self.action {
+            PromptAction::Parallelism {
+                controller,
+                current,
+                ..
+            } => {
+                let current = current.map_or("unset".to_string(), |c| c.to_string());
+                format!("Parallelism for {controller} (now {current}): ")
+            }
+        }
+    }
+}
+
+impl App {
+    /// Keybinds while typing into a prompt
+    pub fn handle_prompt_key(&mut self, key: KeyEvent) {
+        let Some(prompt) = self.prompt.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Enter => {
+                if let Some(prompt) = self.prompt.take() {
+                    self.run_prompt(prompt);
+                }
+            }
+            KeyCode::Backspace => {
+                prompt.input.pop();
+            }
+            KeyCode::Char(c) => prompt.input.push(c),
+            _ => {}
+        }
+    }
+
+    fn run_prompt(&mut self, prompt: Prompt) {
+        match prompt.action {
+            PromptAction::Parallelism {
+                controller, artist, ..
+            } => {
+                let Ok(parallelism) = prompt.input.trim().parse::<i32>() else {
+                    self.notify(
+                        Level::Error,
+                        format!("Parallelism must be a number, not '{}'", prompt.input),
+                    );
+                    return;
+                };
+                if parallelism < 0 {
+                    self.notify(Level::Error, "Parallelism can't be negative");
+                    return;
+                }
+                if let Some(cap) = self.config.parallelism_cap(&artist)
+                    && parallelism > cap
+                {
+                    self.notify(
+                        Level::Error,
+                        format!("Refusing parallelism {parallelism}, {artist}'s jobs are capped at {cap}"),
+                    );
+                    return;
+                }
+                self.run_set_parallelism(controller, parallelism);
+            }
+        }
+    }
+
+    /// Ask for a new parallelism for the selected job's controller
+    pub fn parallelism_key(&mut self) {
+        match self.selected_controller() {
+            Some(Some(controller)) => {
+                let summary = self
+                    .job_summaries()
+                    .into_iter()
+                    .find(|job| job.name == controller);
+                let (artist, current) = summary
+                    .map(|job| (job.artist.clone(), job.parallelism))
+                    .unwrap_or_else(|| ("Unknown".into(), None));
+                self.prompt = Some(Prompt {
+                    action: PromptAction::Parallelism {
+                        controller,
+                        artist,
+                        current,
+                    },
+                    input: String::new(),
+                });
+            }
+            Some(None) => self.notify(Level::Error, "Selected pod doesn't belong to a job"),
+            None => {}
+        }
+    }
+
+    pub fn run_set_parallelism(&mut self, controller: String, parallelism: i32) {
+        self.notify(
+            Level::Info,
+            format!("Setting parallelism of {controller} to {parallelism}..."),
+        );
+        let client = self.client.clone();
+        let namespace = self.config.namespace.clone();
+        let field_manager = self.config.field_manager.clone();
+        let tx = self.tx.clone();
+        let names = self.controller_job_names(&controller);
+        let span = tracing::info_span!("set_parallelism_action", %controller, ?names, parallelism);
+        tokio::spawn(
+            async move {
+                let result =
+                    set_parallelism(client, &namespace, &names, &field_manager, parallelism)
+                        .await
+                        .map_err(|e| describe(&e));
+                let _ = tx.send(Message::Parallelism {
+                    controller,
+                    parallelism,
+                    result,
+                });
+            }
+            .instrument(span),
+        );
+    }
+}
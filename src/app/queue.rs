@@ -0,0 +1,94 @@
+use super::App;
+use super::confirmation::ConfirmAction;
+use super::notifications::Level;
+
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::Paragraph,
+};
+use std::time::Instant;
+
+/// A confirmed action waiting out its grace period, during which it can be undone
+pub struct QueuedAction {
+    pub action: ConfirmAction,
+    pub due: Instant,
+}
+
+impl App {
+    /// Hold a confirmed action back for the configured grace period
+    pub fn queue_action(&mut self, action: ConfirmAction) {
+        let due = Instant::now() + self.config.cancel_grace;
+        self.queued.push(QueuedAction { action, due });
+    }
+
+    /// Run the queued actions whose grace period is over
+    pub fn run_due_actions(&mut self) {
+        let now = Instant::now();
+        let (due, waiting) = std::mem::take(&mut self.queued)
+            .into_iter()
+            .partition(|queued| queued.due <= now);
+        self.queued = waiting;
+        for queued in due {
+            self.run_action(queued.action);
+        }
+    }
+
+    /// Whether mf may quit. Not while a confirmed action is waiting out its grace period, which
+    /// quitting would silently drop.
+    pub fn can_quit(&mut self) -> bool {
+        let pending = self.queued.len();
+        if pending > 0 {
+            self.notify(
+                Level::Error,
+                format!("{pending} cancellation(s) pending, u to undo or wait"),
+            );
+        }
+        pending == 0
+    }
+
+    /// Drop the most recently queued action
+    pub fn undo_key(&mut self) {
+        if let Some(queued) = self.queued.pop() {
+            self.notify(
+                Level::Info,
+                format!("{} undone", queued_label(&queued.action)),
+            );
+        }
+    }
+
+    /// Countdown for the next queued action, one line above the status line
+    pub fn draw_queue_banner(&self, frame: &mut Frame, area: Rect) {
+        let Some(next) = self.queued.iter().min_by_key(|queued| queued.due) else {
+            return;
+        };
+        let left = next.due.saturating_duration_since(Instant::now());
+        let mut text = format!(
+            "{} in {}s - (u) to undo",
+            queued_label(&next.action),
+            left.as_secs() + 1
+        );
+        if self.queued.len() > 1 {
+            text.push_str(&format!(" ({} more queued)", self.queued.len() - 1));
+        }
+        let style = Style::default()
+            .fg(Color::Black)
+            .bg(Color::Yellow)
+            .add_modifier(Modifier::BOLD);
+        frame.render_widget(Paragraph::new(text).style(style), area);
+    }
+}
+
+fn queued_label(action: &ConfirmAction) -> String {
+    match action {
+        ConfirmAction::CancelJob { controllers, jobs } => {
+            format!(
+                "Cancelling {} job(s) of {}",
+                jobs.len(),
+                controllers.join(", ")
+            )
+        }
+        _ => "Queued action".to_string(),
+    }
+}
@@ -0,0 +1,82 @@
+use super::App;
+use super::notifications::Level;
+use crate::config::Config;
+use crate::k8s;
+
+use ratatui::style::{Color, Modifier, Style};
+use std::env;
+
+/// Which artist is running mf: the configured `artist`, then `$USER`, then who the kubeconfig
+/// authenticates as
+pub fn current_artist(config: &Config) -> Option<String> {
+    config
+        .artist
+        .clone()
+        .or_else(|| env::var("USER").ok().filter(|user| !user.is_empty()))
+        .or_else(|| k8s::kubeconfig_user(config))
+}
+
+impl App {
+    /// Whether a pod or job of this artist is shown in the current scope
+    pub fn in_scope(&self, artist: &str) -> bool {
+        match &self.artist {
+            Some(me) if self.mine => me == artist,
+            _ => true,
+        }
+    }
+
+    /// Style for the artist cell of a row, picking out the artist's own rows while showing everyone's
+    pub fn artist_style(&self, artist: &str) -> Style {
+        match &self.artist {
+            Some(me) if !self.mine && me == artist => Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+            _ => Style::default(),
+        }
+    }
+
+    /// Show everyone's jobs when a guessed artist has no pods, e.g. `$USER` isn't what the farm
+    /// labels their pods with
+    pub fn check_guessed_artist(&mut self) {
+        if !std::mem::take(&mut self.artist_guessed) || !self.mine {
+            return;
+        }
+        let Some(artist) = self.artist.clone() else {
+            return;
+        };
+        if !self.items.iter().any(|pod| pod.artist == artist) {
+            self.keep_selected_pod(|app| app.mine = false);
+            self.notify(
+                Level::Info,
+                format!(
+                    "No pods of {artist}, showing everyone's jobs. Set `artist` in your config if that's not you"
+                ),
+            );
+        }
+    }
+
+    /// Switch between the artist's own jobs and everyone's
+    pub fn toggle_mine(&mut self) {
+        let Some(artist) = self.artist.clone() else {
+            self.notify(
+                Level::Error,
+                "Can't tell who you are, set `artist` in your config to see only your jobs",
+            );
+            return;
+        };
+        self.keep_selected_pod(|app| app.mine = !app.mine);
+        if self.mine {
+            self.notify(Level::Info, format!("Showing {artist}'s jobs"));
+        } else {
+            self.notify(Level::Info, "Showing everyone's jobs");
+        }
+    }
+
+    /// Whose jobs are shown, for table titles
+    pub fn scope_label(&self) -> String {
+        match &self.artist {
+            Some(artist) if self.mine => format!("{artist}'s"),
+            _ => "Everyone's".to_string(),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use super::notifications::Level;
+use super::{App, View};
+use crate::data::Data;
+
+use itertools::Itertools;
+
+impl App {
+    /// Mark or unmark the pod under the cursor and move on to the next row
+    pub fn toggle_mark(&mut self) {
+        if self.view != View::Pods {
+            return;
+        }
+        if let Some(name) = self.selected_pod().map(|pod| pod.name.clone())
+            && !self.marked.remove(&name)
+        {
+            self.marked.insert(name);
+        }
+        self.next();
+    }
+
+    /// Start a range at the cursor, or mark every row from the start of the range to the cursor
+    pub fn visual_key(&mut self) {
+        if self.view != View::Pods {
+            return;
+        }
+        let range = self.visual_range();
+        match range {
+            Some(range) => {
+                let names: Vec<String> = self
+                    .visible_pods()
+                    .skip(*range.start())
+                    .take(range.count())
+                    .map(|pod| pod.name.clone())
+                    .collect();
+                self.marked.extend(names);
+                self.visual_anchor = None;
+            }
+            None => self.visual_anchor = self.selected_pod().map(|pod| pod.name.clone()),
+        }
+    }
+
+    /// Mark every pod shown in the pod table
+    pub fn mark_all(&mut self) {
+        if self.view != View::Pods {
+            return;
+        }
+        let names: Vec<String> = self.visible_pods().map(|pod| pod.name.clone()).collect();
+        self.marked.extend(names);
+    }
+
+    /// Whether any pods are marked or a range is being selected
+    pub fn has_selection(&self) -> bool {
+        !self.marked.is_empty() || self.visual_anchor.is_some()
+    }
+
+    /// Forget the marks and any range being selected
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+        self.visual_anchor = None;
+    }
+
+    /// Rows between the range start and the cursor, while a range is being selected
+    pub fn visual_range(&self) -> Option<std::ops::RangeInclusive<usize>> {
+        let anchor = self.visual_anchor.as_ref()?;
+        let start = self.visible_pods().position(|pod| &pod.name == anchor)?;
+        let cursor = self.state.selected()?;
+        Some(start.min(cursor)..=start.max(cursor))
+    }
+
+    /// Marked pods still shown in the pod table, the targets of bulk actions
+    pub fn marked_pods(&self) -> Vec<&Data> {
+        if self.view != View::Pods {
+            return Vec::new();
+        }
+        self.visible_pods()
+            .filter(|pod| self.marked.contains(&pod.name))
+            .collect()
+    }
+
+    /// Pods an action on pods applies to: the marked pods, or else the selected one
+    pub fn target_pods(&self) -> Vec<&Data> {
+        let mut pods = self.marked_pods();
+        if pods.is_empty() {
+            pods.extend(self.selected_pod());
+        }
+        pods
+    }
+
+    /// Controllers an action on jobs applies to: those of the marked pods, or else the selected
+    /// row's. Tells the artist and returns nothing when there are none.
+    pub fn target_controllers(&mut self) -> Option<Vec<String>> {
+        let marked = self.marked_pods();
+        if !marked.is_empty() {
+            let controllers: Vec<String> = marked
+                .iter()
+                .filter_map(|pod| pod.controller.clone())
+                .unique()
+                .collect();
+            if controllers.is_empty() {
+                self.notify(Level::Error, "None of the marked pods belong to a job");
+                return None;
+            }
+            return Some(controllers);
+        }
+        match self.selected_controller() {
+            Some(Some(controller)) => Some(vec![controller]),
+            Some(None) => {
+                self.notify(Level::Error, "Selected pod doesn't belong to a job");
+                None
+            }
+            None => None,
+        }
+    }
+}
@@ -0,0 +1,322 @@
+use ratatui::style::{Color, Style};
+use regex::{Regex, RegexSet};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// How bad a log line is, going by what the DCC printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// Colour a line of this severity is drawn in, under any colours the tool printed itself
+    pub fn style(self) -> Style {
+        match self {
+            Severity::Error => Style::default().fg(Color::Red),
+            Severity::Warning => Style::default().fg(Color::Yellow),
+            Severity::Info => Style::default().fg(Color::Cyan),
+        }
+    }
+}
+
+/// A regex in the config, checked when the config is loaded
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Pattern(Regex);
+
+impl TryFrom<String> for Pattern {
+    type Error = regex::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Regex::new(&value).map(Pattern)
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(pattern: Pattern) -> Self {
+        pattern.to_string()
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0.as_str())
+    }
+}
+
+/// Patterns marking a DCC's log lines as errors, warnings or info, configured in
+/// `[log_patterns.<dcc>]`. A list that is set replaces the built-in one for that DCC.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LogPatterns {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<Vec<Pattern>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<Vec<Pattern>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<Vec<Pattern>>,
+}
+
+/// Error, warning and info patterns of the DCCs run on the farm
+const BUILT_IN: &[(&str, [&[&str]; 3])] = &[
+    (
+        "houdini",
+        [
+            &[
+                r"^\s*Error:",
+                r"^Traceback \(most recent call last\)",
+                r"^hou\.\w*Error\b",
+            ],
+            &[r"^\s*Warning:"],
+            &[r"^\s*Message:"],
+        ],
+    ),
+    (
+        "karma",
+        [
+            &[r"(?i)\b(husk|karma\w*)\b.*\b(error|fatal)\b"],
+            &[r"(?i)\b(husk|karma\w*)\b.*\bwarning\b"],
+            &[r"(?i)\b(husk|karma\w*)\b.*\brender(ing)? (time|complete|finished)\b"],
+        ],
+    ),
+    (
+        "mantra",
+        [
+            &[r"^mantra: .*\b(Error|Unable|Failed)\b"],
+            &[r"^mantra: .*\bWarning\b"],
+            &[r"^mantra: Render Time:"],
+        ],
+    ),
+    (
+        "nuke",
+        [
+            &[r"^ERROR:"],
+            &[r"^WARNING:"],
+            &[
+                r"^Writing .+ took [\d.]+ seconds",
+                r"^Frame \d+ \(\d+ of \d+\)",
+            ],
+        ],
+    ),
+    (
+        "arnold",
+        [
+            &[r"\bERROR\s*\|"],
+            &[r"\bWARNING\s*\|"],
+            &[r"\|\s+render done\b", r"\|\s+\d+% done\b"],
+        ],
+    ),
+    (
+        "redshift",
+        [
+            &[r"(?i)^\[redshift\].*\berror\b"],
+            &[r"(?i)^\[redshift\].*\bwarning\b"],
+            &[r"(?i)^\[redshift\]\s*rendering (time|frame)\b"],
+        ],
+    ),
+    (
+        "blender",
+        [
+            &[r"^Error:", r"^Traceback \(most recent call last\)"],
+            &[r"^Warning:"],
+            &[r"^Fra:\d+ ", r"^Saved: "],
+        ],
+    ),
+];
+
+/// Patterns of one DCC, or of all of them, with a set of regexes per severity
+pub struct Patterns {
+    error: RegexSet,
+    warning: RegexSet,
+    info: RegexSet,
+}
+
+impl Patterns {
+    /// Fails when a set grows past the regex size limit, even though each pattern compiled on
+    /// its own when the config was loaded
+    fn new(error: &[String], warning: &[String], info: &[String]) -> Result<Self, regex::Error> {
+        Ok(Self {
+            error: RegexSet::new(error)?,
+            warning: RegexSet::new(warning)?,
+            info: RegexSet::new(info)?,
+        })
+    }
+
+    /// The worst severity any pattern gives a line, if any matches
+    pub fn classify(&self, line: &str) -> Option<Severity> {
+        [
+            (&self.error, Severity::Error),
+            (&self.warning, Severity::Warning),
+            (&self.info, Severity::Info),
+        ]
+        .into_iter()
+        .find(|(set, _)| set.is_match(line))
+        .map(|(_, severity)| severity)
+    }
+}
+
+/// Tells the severity of log lines from the built-in patterns and those in `log_patterns`, using
+/// those of the DCC a pod runs
+pub struct Classifier {
+    dccs: BTreeMap<String, Patterns>,
+    /// Every DCC's patterns, for pods that don't say which DCC they run or run one without any
+    all: Patterns,
+}
+
+impl Classifier {
+    pub fn new(configured: &BTreeMap<String, LogPatterns>) -> Result<Self, regex::Error> {
+        let mut lists: BTreeMap<String, [Vec<String>; 3]> = BTreeMap::new();
+        for &(dcc, [error, warning, info]) in BUILT_IN {
+            let configured = configured.get(dcc).cloned().unwrap_or_default();
+            lists.insert(
+                dcc.to_string(),
+                [
+                    patterns(configured.error, error),
+                    patterns(configured.warning, warning),
+                    patterns(configured.info, info),
+                ],
+            );
+        }
+        // DCCs mf doesn't know about
+        for (dcc, configured) in configured {
+            if !lists.contains_key(dcc) {
+                let configured = configured.clone();
+                lists.insert(
+                    dcc.clone(),
+                    [
+                        patterns(configured.error, &[]),
+                        patterns(configured.warning, &[]),
+                        patterns(configured.info, &[]),
+                    ],
+                );
+            }
+        }
+        let all = |severity: usize| -> Vec<String> {
+            lists
+                .values()
+                .flat_map(|lists| lists[severity].iter().cloned())
+                .collect()
+        };
+        let all = Patterns::new(&all(0), &all(1), &all(2))?;
+        let dccs = lists
+            .iter()
+            .map(|(dcc, [error, warning, info])| {
+                Ok((dcc.to_lowercase(), Patterns::new(error, warning, info)?))
+            })
+            .collect::<Result<_, regex::Error>>()?;
+        Ok(Classifier { dccs, all })
+    }
+
+    /// Patterns of a DCC, going by the pod's DCC label, or every DCC's when it has none or an
+    /// unknown one
+    pub fn patterns(&self, dcc: Option<&str>) -> &Patterns {
+        dcc.and_then(|dcc| self.dccs.get(&dcc.to_lowercase()))
+            .unwrap_or(&self.all)
+    }
+}
+
+/// The configured patterns when set, or else the built-in ones
+fn patterns(configured: Option<Vec<Pattern>>, built_in: &[&str]) -> Vec<String> {
+    match configured {
+        Some(patterns) => patterns.iter().map(Pattern::to_string).collect(),
+        None => built_in.iter().map(|pattern| pattern.to_string()).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_patterns_compile() {
+        for &(dcc, lists) in BUILT_IN {
+            for pattern in lists.iter().flat_map(|list| list.iter()) {
+                assert!(Regex::new(pattern).is_ok(), "{dcc}: {pattern}");
+            }
+        }
+        Classifier::new(&BTreeMap::new()).unwrap();
+    }
+
+    #[test]
+    fn built_in_patterns_classify_typical_lines() {
+        let classifier = Classifier::new(&BTreeMap::new()).unwrap();
+        let classify = |dcc, line| classifier.patterns(Some(dcc)).classify(line);
+        assert_eq!(
+            classify("nuke", "ERROR: Write1: bad thing"),
+            Some(Severity::Error)
+        );
+        assert_eq!(
+            classify("nuke", "WARNING: Read2: missing"),
+            Some(Severity::Warning)
+        );
+        assert_eq!(
+            classify("arnold", "00:00:01  512MB WARNING | [arnold] meh"),
+            Some(Severity::Warning)
+        );
+        assert_eq!(classify("blender", "Fra:6 Mem:12M"), Some(Severity::Info));
+        assert_eq!(
+            classify("Houdini", "Traceback (most recent call last):"),
+            Some(Severity::Error)
+        );
+        assert_eq!(classify("houdini", "rendering frame 6"), None);
+    }
+
+    #[test]
+    fn a_dcc_only_uses_its_own_patterns() {
+        let classifier = Classifier::new(&BTreeMap::new()).unwrap();
+        // Blender's info pattern, not Houdini's
+        assert_eq!(
+            classifier
+                .patterns(Some("houdini"))
+                .classify("Fra:6 Mem:12M"),
+            None
+        );
+        // Unknown or no DCC falls back to every pattern
+        let all = Some(Severity::Info);
+        assert_eq!(
+            classifier.patterns(Some("maya")).classify("Fra:6 Mem:12M"),
+            all
+        );
+        assert_eq!(classifier.patterns(None).classify("Fra:6 Mem:12M"), all);
+    }
+
+    #[test]
+    fn configured_patterns_replace_built_in_ones_and_add_dccs() {
+        let pattern = |text: &str| Pattern::try_from(text.to_string()).unwrap();
+        let configured = BTreeMap::from([
+            (
+                "nuke".to_string(),
+                LogPatterns {
+                    warning: Some(Vec::new()),
+                    ..LogPatterns::default()
+                },
+            ),
+            (
+                "vray".to_string(),
+                LogPatterns {
+                    error: Some(vec![pattern(r"^\[V-Ray\] error")]),
+                    ..LogPatterns::default()
+                },
+            ),
+        ]);
+        let classifier = Classifier::new(&configured).unwrap();
+        let nuke = classifier.patterns(Some("nuke"));
+        assert_eq!(nuke.classify("WARNING: Read2: missing"), None);
+        assert_eq!(nuke.classify("ERROR: Write1"), Some(Severity::Error));
+        let vray = classifier.patterns(Some("vray"));
+        assert_eq!(
+            vray.classify("[V-Ray] error: no license"),
+            Some(Severity::Error)
+        );
+        assert_eq!(
+            classifier
+                .patterns(None)
+                .classify("[V-Ray] error: no license"),
+            Some(Severity::Error)
+        );
+    }
+}
@@ -1,21 +1,34 @@
-use super::App;
-use crate::k8s::{cancel_jobs, is_host_schedulable, set_host_schedulable};
+use super::columns::{Column, SORT_COLUMNS, fit, truncate};
+use super::confirmation::{ConfirmAction, PodTarget, avoided_nodes};
+use super::notifications::Level;
+use super::{App, Message, View};
+use crate::data::{Data, JobData};
+use crate::k8s::{
+    JobRef, avoid_label_key, avoid_node, cancel_jobs, delete_pod, describe, is_host_schedulable,
+    set_host_schedulable,
+};
 
 use humantime::format_duration;
+use itertools::Itertools;
 use k8s_openapi::chrono::{DateTime, Utc};
+use kube::Client;
 use ratatui::{
     Frame,
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Modifier, Style},
-    widgets::{Block, Cell, Paragraph, Row, Table},
+    widgets::{Block, Cell, Paragraph, Row, Table, TableState},
 };
 use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::Instrument;
+
+/// How often the workstation's farm membership is re-checked
+const HOST_POLL_INTERVAL: Duration = Duration::from_secs(5);
 
 impl App {
-    /// Main table view
-    pub fn draw_table(&mut self, frame: &mut Frame) {
+    /// Main table view, listing either pods or jobs
+    pub fn draw_table(&mut self, frame: &mut Frame, area: Rect) {
         // Define Regions
-        let area = frame.area();
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
@@ -24,56 +37,32 @@ impl App {
                 Constraint::Length(3),
             ])
             .split(area);
-        // Main job table
-        let rows = self.items.iter().map(|item| {
-            let age = item
-                .created_at
-                .as_ref()
-                .map(format_age)
-                .unwrap_or_else(|| "n/a".into());
-            let run_time = item
-                .started_at
-                .as_ref()
-                .map(|s| format_run_time(&s, &item.finished_at.unwrap_or_else(Utc::now)))
-                .unwrap_or_else(|| "n/a".into());
-            let style = status_colors(&item.status);
-            Row::new(vec![
-                item.name.clone(),
-                item.status.clone(),
-                item.artist.clone(),
-                item.node.clone(),
-                run_time,
-                age,
-            ])
-            .style(style)
-        });
-        let columns = [
-            ("Name", Constraint::Percentage(50)),
-            ("Status", Constraint::Percentage(10)),
-            ("Artist", Constraint::Percentage(10)),
-            ("Node", Constraint::Percentage(10)),
-            ("Run Time", Constraint::Percentage(10)),
-            ("Age", Constraint::Percentage(10)),
-        ];
-        let table = Table::new(rows, columns.iter().map(|(_, c)| *c))
-            .header(Row::new(
-                columns.iter().map(|(title, _)| Cell::from(*title)),
-            ))
-            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
-            .highlight_symbol("⇝")
-            .block(Block::bordered());
-        frame.render_stateful_widget(table, chunks[1], &mut self.state);
-        let host_status = self
-            .rt
-            .block_on(is_host_schedulable(self.client.clone(), None));
-        let host_status = match host_status {
-            Ok(false) => "not on the farm. Press (p) to return it to the farm.".to_string(),
-            Ok(true) => "on the farm. Press (o) to check out your node.".to_string(),
-            Err(_) => "not part of the cluster.".to_string(),
+        match self.view {
+            View::Pods => self.draw_pod_table(frame, chunks[1]),
+            View::Jobs => self.draw_job_table(frame, chunks[1]),
+            View::Frames => self.draw_frames(frame, chunks[1]),
+        }
+        let host_status = match &self.host_schedulable {
+            Some(Ok(false)) => "not on the farm. Press (p) to return it to the farm.".to_string(),
+            Some(Ok(true)) => "on the farm. Press (o) to check out your node.".to_string(),
+            Some(Err(_)) => "not part of the cluster.".to_string(),
+            None => "being checked...".to_string(),
         };
-        let info =
-            Paragraph::new("MF - (q) to quit, (Enter) to view logs. (Shift + D) to cancel a job.")
-                .block(Block::bordered());
+        let help = match (self.view, &self.job_filter) {
+            (View::Pods, None) => {
+                "MF - (q) to quit, (Enter) to view logs, (/) to filter, (m) mine/everyone's. (Tab) for jobs. (x) to delete a pod, (e) to export, (z) to suspend/resume, (Shift + D) to cancel a job."
+            }
+            (View::Pods, Some(_)) => {
+                "MF - (Esc) back to jobs, (Enter) to view logs, (/) to filter. (x) to delete a pod, (z) to suspend/resume, (Shift + D) to cancel the job."
+            }
+            (View::Jobs, _) => {
+                "MF - (q) to quit, (Enter) to view pods, (f) for frames, (m) mine/everyone's. (Tab) for pods. (z) to suspend/resume, (Shift + P) parallelism, (Shift + D) to cancel a job."
+            }
+            (View::Frames, _) => {
+                "MF - (Esc) back to jobs, (h/j/k/l) to move, (Enter) to view the frame's logs. (x) to delete its pod, (z) to suspend/resume, (Shift + D) to cancel the job."
+            }
+        };
+        let info = Paragraph::new(help).block(Block::bordered());
         let checkout_status =
             Paragraph::new(format!("Your node is {}", &host_status)).block(Block::bordered());
         frame.render_widget(info, chunks[0]);
@@ -81,77 +70,404 @@ impl App {
         self.show_confirmation(frame);
     }
 
-    /// Spawns the confirmation for job deletion, to kill all jobs that share the same "controller"
-    /// id
-    pub fn delete_key(&mut self) {
-        if let Some(controller) = self
-            .state
-            .selected()
-            .and_then(|i| self.items.get(i))
-            .and_then(|idx| idx.controller.as_ref())
-        {
-            self.pending_confirmation = Some(crate::app::ConfirmAction::CancelJob {
-                controller: controller.clone(),
+    /// Pod table, narrowed to one job when drilled down from the jobs view
+    fn draw_pod_table(&mut self, frame: &mut Frame, area: Rect) {
+        let columns = &self.config.columns;
+        let pods: Vec<&Data> = self.visible_pods().collect();
+        let range = self.visual_range();
+        let marked: Vec<bool> = pods
+            .iter()
+            .enumerate()
+            .map(|(i, pod)| {
+                self.marked.contains(&pod.name) || range.as_ref().is_some_and(|r| r.contains(&i))
+            })
+            .collect();
+        let texts: Vec<Vec<String>> = pods
+            .iter()
+            .zip(&marked)
+            .map(|(pod, marked)| {
+                columns
+                    .iter()
+                    .map(|column| match column {
+                        Column::Name if *marked => format!("✔ {}", pod.name),
+                        _ => column.text(pod),
+                    })
+                    .collect()
+            })
+            .collect();
+        // Inside the borders and the highlight symbol
+        let fitted = fit(columns, &texts, area.width.saturating_sub(3));
+        let rows = pods
+            .iter()
+            .zip(marked)
+            .zip(&texts)
+            .map(|((pod, marked), text)| {
+                let mut style = status_colors(&pod.status, &pod.phase);
+                if marked {
+                    style = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+                }
+                let cells = fitted.iter().map(|&(i, width)| {
+                    let cell = Cell::from(truncate(&text[i], usize::from(width)));
+                    match columns[i] {
+                        Column::Artist => cell.style(self.artist_style(&pod.artist)),
+                        _ => cell,
+                    }
+                });
+                Row::new(cells).style(style)
             });
-            self.confirmation_popup = true;
+        let arrow = if self.sort_reversed { "▲" } else { "▼" };
+        let header = fitted.iter().map(|&(i, _)| {
+            let column = &columns[i];
+            if *column == self.sort {
+                Cell::from(format!("{} {arrow}", column.header()))
+            } else {
+                Cell::from(column.header().to_string())
+            }
+        });
+        let widths = fitted.iter().map(|&(_, width)| Constraint::Length(width));
+        let mut title = match &self.job_filter {
+            Some(job) => format!("Pods of {job}"),
+            None => format!("{} pods", self.scope_label()),
+        };
+        if !self.filter.is_empty() {
+            let shown = self.visible_pods().count();
+            title.push_str(&format!(" [{}] ({shown} shown)", self.filter.text));
+        }
+        let marked = self.marked_pods().len();
+        if marked > 0 {
+            title.push_str(&format!(" ({marked} marked, Esc to clear)"));
+        } else if self.visual_anchor.is_some() {
+            title.push_str(" (selecting a range, V to mark it)");
+        }
+        if self.sort != SORT_COLUMNS[0] || self.sort_reversed {
+            title.push_str(&format!(" sorted by {} {arrow}", self.sort.header()));
         }
+        let block = Block::bordered().title(title);
+        let table = Table::new(rows, widths)
+            .header(Row::new(header))
+            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("⇝")
+            .block(block);
+        frame.render_stateful_widget(table, area, &mut self.state);
     }
 
-    pub fn run_cancel_jobs(&mut self, controller: String) {
-        let client = self.client.clone();
-        self.rt.spawn(async move {
-            if let Err(e) = cancel_jobs(client, &controller).await {
-                eprintln!("Failed to cancel job {}", e);
+    /// Pods shown in the pod table, in the order they are shown
+    pub fn visible_pods(&self) -> impl Iterator<Item = &Data> {
+        self.visible.iter().map(|&i| &self.items[i])
+    }
+
+    /// Filter and sort the pod table again. Called whenever the pods, the job drilled down into,
+    /// the scope, the filter or the sort change, so drawing doesn't re-sort every frame.
+    pub fn update_visible_pods(&mut self) {
+        let mut visible: Vec<usize> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| match &self.job_filter {
+                Some(job) => item.controller.as_ref() == Some(job),
+                None => true,
+            })
+            .filter(|(_, item)| self.in_scope(&item.artist))
+            .filter(|(_, item)| self.filter.matches(item))
+            .map(|(i, _)| i)
+            .collect();
+        // Stable, so pods that tie stay newest first
+        let now = Utc::now();
+        visible.sort_by(|&a, &b| {
+            let order = self.sort.compare(&self.items[a], &self.items[b], now);
+            if self.sort_reversed {
+                order.reverse()
+            } else {
+                order
             }
         });
+        self.visible = visible;
+    }
+
+    /// Pod under the cursor in the pod table, or of the frame under the cursor in the frame grid
+    pub fn selected_pod(&self) -> Option<&Data> {
+        if self.view == View::Frames {
+            return self.selected_frame_pod();
+        }
+        self.state
+            .selected()
+            .and_then(|i| self.visible_pods().nth(i))
+    }
+
+    /// Controller of the selected row: `None` when nothing is selected, `Some(None)` for a pod
+    /// that doesn't belong to a job
+    pub fn selected_controller(&self) -> Option<Option<String>> {
+        match self.view {
+            View::Pods => self.selected_pod().map(|pod| pod.controller.clone()),
+            View::Jobs => self.selected_job().map(|job| Some(job.name.clone())),
+            View::Frames => Some(self.job_filter.clone()),
+        }
+    }
+
+    /// Spawns the confirmation for job deletion, listing exactly the jobs of the marked pods'
+    /// controllers, or the selected one, that would be deleted
+    pub fn delete_key(&mut self) {
+        let Some(controllers) = self.target_controllers() else {
+            return;
+        };
+        let jobs: Vec<JobData> = controllers
+            .iter()
+            .flat_map(|controller| self.controller_jobs(controller))
+            .unique_by(|job| &job.name)
+            .cloned()
+            .collect();
+        if jobs.is_empty() {
+            self.notify(
+                Level::Error,
+                format!("No jobs found for {}", controllers.join(", ")),
+            );
+            return;
+        }
+        self.pending_confirmation = Some(ConfirmAction::CancelJob { controllers, jobs });
+        self.confirmation_popup = true;
+    }
+
+    pub fn run_cancel_jobs(&mut self, controllers: Vec<String>, jobs: Vec<JobData>, dry_run: bool) {
+        let verb = if dry_run {
+            "Dry run cancelling"
+        } else {
+            "Cancelling"
+        };
+        self.notify(
+            Level::Info,
+            format!(
+                "{verb} {} job(s) of {}...",
+                jobs.len(),
+                controllers.join(", ")
+            ),
+        );
+        let client = self.client.clone();
+        let namespace = self.config.namespace.clone();
+        let tx = self.tx.clone();
+        let targets: Vec<JobRef> = jobs
+            .into_iter()
+            .map(|job| JobRef {
+                name: job.name,
+                uid: job.uid,
+            })
+            .collect();
+        let span = tracing::info_span!("cancel_jobs_action", ?controllers, dry_run);
+        tokio::spawn(
+            async move {
+                let results = cancel_jobs(client, &namespace, &targets, dry_run)
+                    .await
+                    .into_iter()
+                    .map(|(job, result)| (job, result.map_err(|e| describe(&e))))
+                    .collect();
+                let _ = tx.send(Message::CancelJobs {
+                    controllers,
+                    dry_run,
+                    results,
+                });
+            }
+            .instrument(span),
+        );
+    }
+
+    /// Spawns the confirmation for deleting just the marked pods, or the selected one
+    pub fn delete_pod_key(&mut self) {
+        if self.view == View::Jobs {
+            self.notify(Level::Error, "Select a pod to delete, not a job");
+            return;
+        }
+        let pods = self.target_pods();
+        if pods.is_empty() {
+            return;
+        }
+        let pods = pods
+            .into_iter()
+            .map(|pod| PodTarget {
+                name: pod.name.clone(),
+                node: Some(pod.node.clone()).filter(|node| node != "N/A"),
+                artist: pod.artist.clone(),
+            })
+            .collect();
+        self.pending_confirmation = Some(ConfirmAction::DeletePods { pods });
+        self.confirmation_popup = true;
+    }
+
+    /// Delete pods, then optionally label their nodes so the farm scheduler keeps the artists'
+    /// pods off them
+    pub fn run_delete_pods(&mut self, pods: Vec<PodTarget>, avoid: bool) {
+        self.notify(Level::Info, format!("Deleting {} pod(s)...", pods.len()));
+        let avoid = if avoid {
+            avoided_nodes(&pods)
+        } else {
+            Vec::new()
+        };
+        let client = self.client.clone();
+        let namespace = self.config.namespace.clone();
+        let avoid_label = self.config.avoid_node_label.clone();
+        let field_manager = self.config.field_manager.clone();
+        let tx = self.tx.clone();
+        let span = tracing::info_span!("delete_pods_action", count = pods.len());
+        tokio::spawn(
+            async move {
+                for pod in pods {
+                    let result = delete_pod(client.clone(), &namespace, &pod.name)
+                        .await
+                        .map_err(|e| describe(&e));
+                    let _ = tx.send(Message::DeletePod {
+                        pod: pod.name,
+                        result,
+                    });
+                }
+                for (node, artist) in avoid {
+                    let result = match avoid_label_key(&avoid_label, &artist) {
+                        Ok(key) => avoid_node(client.clone(), &node, &key, &field_manager).await,
+                        Err(e) => Err(e),
+                    }
+                    .map_err(|e| describe(&e));
+                    let _ = tx.send(Message::AvoidNode {
+                        node,
+                        artist,
+                        result,
+                    });
+                }
+            }
+            .instrument(span),
+        );
     }
 
     pub fn checkout_key(&mut self, checkout: bool) {
-        self.pending_confirmation = Some(crate::app::ConfirmAction::CheckoutNode {
+        self.pending_confirmation = Some(ConfirmAction::CheckoutNode {
             schedulable: (checkout),
         });
         self.confirmation_popup = true;
     }
 
     pub fn run_checkout(&mut self, checkout: bool) {
-        if let Err(e) = self
-            .rt
-            .block_on(set_host_schedulable(self.client.clone(), None, checkout))
-        {
-            eprintln!("Failed to mark host schedulable: {}", e);
-        }
+        let client = self.client.clone();
+        let key = self.config.checkout_label.clone();
+        let field_manager = self.config.field_manager.clone();
+        let tx = self.tx.clone();
+        let span = tracing::info_span!("checkout_action", schedulable = checkout);
+        tokio::spawn(
+            async move {
+                let result = set_host_schedulable(client.clone(), &key, &field_manager, checkout)
+                    .await
+                    .map_err(|e| describe(&e));
+                let _ = tx.send(Message::Checkout {
+                    schedulable: checkout,
+                    result,
+                });
+                let status = is_host_schedulable(client, &key)
+                    .await
+                    .map_err(|e| describe(&e));
+                let _ = tx.send(Message::HostSchedulable(status));
+            }
+            .instrument(span),
+        );
     }
 
     /// Next line in table keymap
     pub fn next(&mut self) {
-        if let Some(i) = self.state.selected() {
-            if i + 1 < self.items.len() {
-                self.state.select(Some(i + 1));
+        match self.view {
+            View::Pods => {
+                let len = self.visible_pods().count();
+                step(&mut self.state, len, true);
+            }
+            View::Jobs => {
+                let len = self.job_summaries().len();
+                step(&mut self.job_state, len, true);
             }
-        } else if !self.items.is_empty() {
-            self.state.select(Some(0));
+            View::Frames => self.move_frame_row(true),
         }
     }
 
     /// Prev line in table keymap
     pub fn previous(&mut self) {
-        if let Some(i) = self.state.selected() {
-            if i > 0 {
-                self.state.select(Some(i - 1));
+        match self.view {
+            View::Pods => {
+                let len = self.visible_pods().count();
+                step(&mut self.state, len, false);
             }
-        } else if !self.items.is_empty() {
-            self.state.select(Some(0));
+            View::Jobs => {
+                let len = self.job_summaries().len();
+                step(&mut self.job_state, len, false);
+            }
+            View::Frames => self.move_frame_row(false),
+        }
+    }
+
+    /// Sort the pod table by the next of [`SORT_COLUMNS`]
+    pub fn cycle_sort(&mut self) {
+        let next = SORT_COLUMNS
+            .iter()
+            .position(|column| *column == self.sort)
+            .map_or(0, |i| (i + 1) % SORT_COLUMNS.len());
+        self.keep_selected_pod(|app| {
+            app.sort = SORT_COLUMNS[next].clone();
+            app.sort_reversed = false;
+        });
+    }
+
+    /// Flip the pod table's sort order
+    pub fn reverse_sort(&mut self) {
+        self.keep_selected_pod(|app| app.sort_reversed = !app.sort_reversed);
+    }
+
+    /// Switch between the pod and job tables
+    pub fn toggle_view(&mut self) {
+        self.job_filter = None;
+        self.update_visible_pods();
+        self.view = match self.view {
+            View::Pods => View::Jobs,
+            View::Jobs | View::Frames => View::Pods,
+        };
+    }
+}
+
+/// Move a table cursor one row, clamped to the table
+fn step(state: &mut TableState, len: usize, down: bool) {
+    match state.selected() {
+        Some(i) if down && i + 1 < len => state.select(Some(i + 1)),
+        Some(i) if !down && i > 0 => state.select(Some(i - 1)),
+        Some(_) => {}
+        None if len > 0 => state.select(Some(0)),
+        None => {}
+    }
+}
+
+/// Periodically check whether this workstation is on the farm, until the app goes away
+pub async fn poll_host(client: Client, key: String, tx: UnboundedSender<Message>) {
+    loop {
+        let status = is_host_schedulable(client.clone(), &key)
+            .await
+            .map_err(|e| describe(&e));
+        if tx.send(Message::HostSchedulable(status)).is_err() {
+            break;
         }
+        tokio::time::sleep(HOST_POLL_INTERVAL).await;
     }
 }
 
-/// Status to colors for table view
-pub fn status_colors(status: &str) -> Style {
+/// Status to colors for table view. Statuses without a color of their own use the phase's.
+pub fn status_colors(status: &str, phase: &str) -> Style {
     match status {
         "Running" => Style::default().fg(ratatui::style::Color::Green),
-        "Pending" => Style::default().fg(ratatui::style::Color::Blue),
-        "Succeeded" => Style::default().fg(ratatui::style::Color::DarkGray),
-        "Failed" | "CrashLoopBackoff" => Style::default().fg(ratatui::style::Color::Red),
+        "Pending" | "ContainerCreating" | "PodInitializing" => {
+            Style::default().fg(ratatui::style::Color::Blue)
+        }
+        "Succeeded" | "Completed" => Style::default().fg(ratatui::style::Color::DarkGray),
+        "Terminating" => Style::default().fg(ratatui::style::Color::Yellow),
+        "Failed"
+        | "CrashLoopBackOff"
+        | "OOMKilled"
+        | "Evicted"
+        | "Unschedulable"
+        | "ImagePullBackOff"
+        | "ErrImagePull"
+        | "CreateContainerConfigError" => Style::default().fg(ratatui::style::Color::Red),
+        s if s.starts_with("Error") || s.starts_with("ExitCode") || s.starts_with("Signal") => {
+            Style::default().fg(ratatui::style::Color::Red)
+        }
+        _ if status != phase => status_colors(phase, phase),
         _ => Style::default(),
     }
 }
@@ -0,0 +1,53 @@
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+/// MF - Mana Farm. A TUI for DCC workloads dispatched to Kubernetes.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Config profile to use, as defined in the site or user config file
+    #[arg(short, long, env = "MF_PROFILE", global = true)]
+    pub profile: Option<String>,
+
+    /// Extra config file, layered on top of the site and user config files
+    #[arg(long, env = "MF_CONFIG", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Namespace the farm pods and jobs live in [default: dcc]
+    #[arg(short, long, env = "MF_NAMESPACE", global = true)]
+    pub namespace: Option<String>,
+
+    /// Label selector used to find farm pods [default: managed-by=oom-scheduler]
+    #[arg(short = 'l', long, env = "MF_SELECTOR", global = true)]
+    pub selector: Option<String>,
+
+    /// Node label toggled when checking your workstation in and out of the farm [default: oom/schedulable]
+    #[arg(long, env = "MF_CHECKOUT_LABEL", global = true)]
+    pub checkout_label: Option<String>,
+
+    /// Path to a kubeconfig file, instead of the default lookup
+    #[arg(long, env = "MF_KUBECONFIG", global = true)]
+    pub kubeconfig: Option<PathBuf>,
+
+    /// Kubeconfig context to use, instead of the current context
+    #[arg(long, env = "MF_CONTEXT", global = true)]
+    pub context: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the effective configuration after merging files, environment and flags
+    Show,
+}
@@ -0,0 +1,450 @@
+use crate::app::columns::Column;
+use crate::app::severity::{Classifier, LogPatterns};
+use crate::cli::Cli;
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, eyre},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::{env, fs};
+
+const SITE_CONFIG: &str = "/etc/mf/config.toml";
+
+/// Effective settings for a session, resolved from config files, environment and flags.
+#[derive(Clone, Debug, Serialize)]
+pub struct Config {
+    #[serde(skip)]
+    pub profile: Option<String>,
+    #[serde(skip)]
+    pub sources: Vec<PathBuf>,
+    pub namespace: String,
+    pub selector: String,
+    /// Label selector for the farm's Jobs, every Job in the namespace when empty. Separate from
+    /// `selector`, since the farm may only label the pods.
+    pub job_selector: String,
+    pub artist_label: String,
+    /// Whose jobs the "mine" scope shows, instead of `$USER` or the kubeconfig user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    pub checkout_label: String,
+    /// Node label asking the farm scheduler to keep an artist's pods off a node, `{artist}` is
+    /// replaced with the artist
+    pub avoid_node_label: String,
+    pub field_manager: String,
+    #[serde(with = "duration")]
+    pub refresh_interval: Duration,
+    pub log_tail_lines: i64,
+    /// Most log lines kept while viewing a pod's logs, older ones are dropped
+    pub log_buffer_lines: usize,
+    /// Pod label naming the DCC a pod runs, whose `log_patterns` its log lines are checked with
+    pub dcc_label: String,
+    /// Error, warning and info patterns of log lines per DCC, on top of the built-in ones
+    pub log_patterns: BTreeMap<String, LogPatterns>,
+    /// How long a confirmed cancellation waits, and can be undone, before anything is deleted
+    #[serde(with = "duration")]
+    pub cancel_grace: Duration,
+    /// Highest parallelism anyone may set on a job from mf, unlimited when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_parallelism: Option<i32>,
+    /// Per artist overrides of `max_parallelism`, keyed by the job's artist
+    pub artist_max_parallelism: BTreeMap<String, i32>,
+    /// Columns of the pod table, left to right. Later ones are dropped first when the terminal
+    /// is too narrow.
+    pub columns: Vec<Column>,
+    /// Named filters for the pod table, used as `@name` in the filter bar
+    pub filter_presets: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kubeconfig: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            profile: None,
+            sources: Vec::new(),
+            namespace: "dcc".into(),
+            selector: "managed-by=oom-scheduler".into(),
+            job_selector: String::new(),
+            artist_label: "oom/artist".into(),
+            artist: None,
+            checkout_label: "oom/schedulable".into(),
+            avoid_node_label: "oom/avoid-{artist}".into(),
+            field_manager: "flux-client-side-apply".into(),
+            refresh_interval: Duration::from_millis(500),
+            log_tail_lines: 100,
+            log_buffer_lines: 10_000,
+            dcc_label: "oom/dcc".into(),
+            log_patterns: BTreeMap::new(),
+            cancel_grace: Duration::from_secs(10),
+            max_parallelism: None,
+            artist_max_parallelism: BTreeMap::new(),
+            columns: Column::default_set(),
+            filter_presets: BTreeMap::new(),
+            kubeconfig: None,
+            context: None,
+        }
+    }
+}
+
+impl Config {
+    /// Layer the site config, user config and any `--config` file, then the selected profile from
+    /// each of those, then environment and flags on top of the built in defaults.
+    pub fn load(cli: &Cli) -> Result<Self> {
+        let mut paths = vec![PathBuf::from(SITE_CONFIG)];
+        if let Some(dir) = xdg_dir("XDG_CONFIG_HOME", ".config") {
+            paths.push(dir.join("mf").join("config.toml"));
+        }
+        paths.extend(cli.config.clone());
+
+        let mut files = Vec::new();
+        let mut sources = Vec::new();
+        for path in paths {
+            match fs::read_to_string(&path) {
+                Ok(text) => {
+                    let file = ConfigFile::parse(&text)
+                        .wrap_err_with(|| format!("parsing config file {}", path.display()))?;
+                    files.push(file);
+                    sources.push(path);
+                }
+                // Site and user files are optional, an explicit --config file is not
+                Err(e) if e.kind() == ErrorKind::NotFound && cli.config.as_ref() != Some(&path) => {
+                }
+                Err(e) => {
+                    return Err(e)
+                        .wrap_err_with(|| format!("reading config file {}", path.display()));
+                }
+            }
+        }
+
+        let mut config = Self::from_files(files, cli)?;
+        config.sources = sources;
+        Ok(config)
+    }
+
+    /// Merge parsed config files, lowest first: the top-level keys of each, then the selected
+    /// profile from each, then environment and flags.
+    fn from_files(mut files: Vec<ConfigFile>, cli: &Cli) -> Result<Self> {
+        let profile = cli
+            .profile
+            .clone()
+            .or_else(|| files.iter().rev().find_map(|f| f.default_profile.clone()));
+        let mut layer = Layer::default();
+        for file in &mut files {
+            layer.merge(std::mem::take(&mut file.base));
+        }
+        if let Some(name) = &profile {
+            let mut found = false;
+            for file in &mut files {
+                if let Some(overrides) = file.profiles.remove(name) {
+                    layer.merge(overrides);
+                    found = true;
+                }
+            }
+            if !found {
+                return Err(eyre!(
+                    "unknown profile '{name}', it isn't defined in any config file"
+                ));
+            }
+        }
+        layer.merge(Layer::from(cli));
+
+        let mut config = layer.resolve();
+        config.profile = profile;
+        // Each pattern compiled on its own while parsing, but their combined sets can still
+        // grow past the regex size limit
+        Classifier::new(&config.log_patterns).wrap_err("building the log_patterns")?;
+        Ok(config)
+    }
+
+    /// Render the effective values as TOML, for `mf config show`.
+    pub fn show(&self) -> Result<String> {
+        let mut out = format!(
+            "# profile: {}\n",
+            self.profile.as_deref().unwrap_or("(none)")
+        );
+        for source in &self.sources {
+            out.push_str(&format!("# source: {}\n", source.display()));
+        }
+        out.push_str(&toml::to_string(self)?);
+        Ok(out)
+    }
+
+    /// Parallelism cap for an artist's jobs, if there is one
+    pub fn parallelism_cap(&self, artist: &str) -> Option<i32> {
+        self.artist_max_parallelism
+            .get(artist)
+            .copied()
+            .or(self.max_parallelism)
+    }
+}
+
+/// A single config file: shared settings at the top level plus named profiles.
+#[derive(Debug, Default)]
+struct ConfigFile {
+    default_profile: Option<String>,
+    base: Layer,
+    profiles: BTreeMap<String, Layer>,
+}
+
+impl ConfigFile {
+    fn parse(text: &str) -> Result<Self> {
+        let mut table: toml::Table = toml::from_str(text)?;
+        let default_profile = match table.remove("default_profile") {
+            Some(value) => Some(value.try_into()?),
+            None => None,
+        };
+        let profiles = match table.remove("profiles") {
+            Some(value) => value.try_into()?,
+            None => BTreeMap::new(),
+        };
+        let base = table.try_into()?;
+        Ok(Self {
+            default_profile,
+            base,
+            profiles,
+        })
+    }
+}
+
+/// A partial set of settings; unset fields fall through to the layer below.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Layer {
+    namespace: Option<String>,
+    selector: Option<String>,
+    job_selector: Option<String>,
+    artist_label: Option<String>,
+    artist: Option<String>,
+    checkout_label: Option<String>,
+    avoid_node_label: Option<String>,
+    field_manager: Option<String>,
+    #[serde(default, deserialize_with = "duration::deserialize_option")]
+    refresh_interval: Option<Duration>,
+    log_tail_lines: Option<i64>,
+    log_buffer_lines: Option<usize>,
+    dcc_label: Option<String>,
+    log_patterns: Option<BTreeMap<String, LogPatterns>>,
+    #[serde(default, deserialize_with = "duration::deserialize_option")]
+    cancel_grace: Option<Duration>,
+    max_parallelism: Option<i32>,
+    artist_max_parallelism: Option<BTreeMap<String, i32>>,
+    columns: Option<Vec<Column>>,
+    filter_presets: Option<BTreeMap<String, String>>,
+    kubeconfig: Option<PathBuf>,
+    context: Option<String>,
+}
+
+impl Layer {
+    fn merge(&mut self, other: Layer) {
+        self.namespace = other.namespace.or(self.namespace.take());
+        self.selector = other.selector.or(self.selector.take());
+        self.job_selector = other.job_selector.or(self.job_selector.take());
+        self.artist_label = other.artist_label.or(self.artist_label.take());
+        self.artist = other.artist.or(self.artist.take());
+        self.checkout_label = other.checkout_label.or(self.checkout_label.take());
+        self.avoid_node_label = other.avoid_node_label.or(self.avoid_node_label.take());
+        self.field_manager = other.field_manager.or(self.field_manager.take());
+        self.refresh_interval = other.refresh_interval.or(self.refresh_interval);
+        self.log_tail_lines = other.log_tail_lines.or(self.log_tail_lines);
+        self.log_buffer_lines = other.log_buffer_lines.or(self.log_buffer_lines);
+        self.dcc_label = other.dcc_label.or(self.dcc_label.take());
+        self.log_patterns = merge_map(self.log_patterns.take(), other.log_patterns);
+        self.cancel_grace = other.cancel_grace.or(self.cancel_grace);
+        self.max_parallelism = other.max_parallelism.or(self.max_parallelism);
+        self.artist_max_parallelism = merge_map(
+            self.artist_max_parallelism.take(),
+            other.artist_max_parallelism,
+        );
+        self.columns = other.columns.or(self.columns.take());
+        self.filter_presets = merge_map(self.filter_presets.take(), other.filter_presets);
+        self.kubeconfig = other.kubeconfig.or(self.kubeconfig.take());
+        self.context = other.context.or(self.context.take());
+    }
+
+    fn resolve(self) -> Config {
+        let defaults = Config::default();
+        Config {
+            namespace: self.namespace.unwrap_or(defaults.namespace),
+            selector: self.selector.unwrap_or(defaults.selector),
+            job_selector: self.job_selector.unwrap_or(defaults.job_selector),
+            artist_label: self.artist_label.unwrap_or(defaults.artist_label),
+            artist: self.artist,
+            checkout_label: self.checkout_label.unwrap_or(defaults.checkout_label),
+            avoid_node_label: self.avoid_node_label.unwrap_or(defaults.avoid_node_label),
+            field_manager: self.field_manager.unwrap_or(defaults.field_manager),
+            refresh_interval: self.refresh_interval.unwrap_or(defaults.refresh_interval),
+            log_tail_lines: self.log_tail_lines.unwrap_or(defaults.log_tail_lines),
+            log_buffer_lines: self.log_buffer_lines.unwrap_or(defaults.log_buffer_lines),
+            dcc_label: self.dcc_label.unwrap_or(defaults.dcc_label),
+            log_patterns: self.log_patterns.unwrap_or(defaults.log_patterns),
+            cancel_grace: self.cancel_grace.unwrap_or(defaults.cancel_grace),
+            max_parallelism: self.max_parallelism.or(defaults.max_parallelism),
+            artist_max_parallelism: self
+                .artist_max_parallelism
+                .unwrap_or(defaults.artist_max_parallelism),
+            columns: self.columns.unwrap_or(defaults.columns),
+            filter_presets: self.filter_presets.unwrap_or(defaults.filter_presets),
+            kubeconfig: self.kubeconfig,
+            context: self.context,
+            ..defaults
+        }
+    }
+}
+
+/// Tables are merged key by key, so e.g. a user file adding a filter preset keeps the site's
+fn merge_map<V>(
+    lower: Option<BTreeMap<String, V>>,
+    upper: Option<BTreeMap<String, V>>,
+) -> Option<BTreeMap<String, V>> {
+    match (lower, upper) {
+        (Some(mut lower), Some(upper)) => {
+            lower.extend(upper);
+            Some(lower)
+        }
+        (lower, upper) => upper.or(lower),
+    }
+}
+
+impl From<&Cli> for Layer {
+    fn from(cli: &Cli) -> Self {
+        Self {
+            namespace: cli.namespace.clone(),
+            selector: cli.selector.clone(),
+            checkout_label: cli.checkout_label.clone(),
+            kubeconfig: cli.kubeconfig.clone(),
+            context: cli.context.clone(),
+            ..Layer::default()
+        }
+    }
+}
+
+/// Resolve an XDG base directory, falling back to `$HOME/<fallback>` when the variable is unset.
+pub fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))
+}
+
+/// Durations are written in config files as humantime strings, e.g. "500ms" or "2s".
+mod duration {
+    use serde::{Deserialize, Deserializer, Serializer, de};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&humantime::format_duration(*value).to_string())
+    }
+
+    pub fn deserialize_option<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| humantime::parse_duration(&s).map_err(de::Error::custom))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn files(texts: &[&str]) -> Vec<ConfigFile> {
+        texts
+            .iter()
+            .map(|text| ConfigFile::parse(text).unwrap())
+            .collect()
+    }
+
+    fn cli(args: &[&str]) -> Cli {
+        Cli::parse_from(std::iter::once("mf").chain(args.iter().copied()))
+    }
+
+    const SITE: &str = r#"
+        namespace = "site"
+        field_manager = "site-manager"
+        default_profile = "prod"
+
+        [filter_presets]
+        failed = "status:Failed"
+
+        [profiles.prod]
+        selector = "farm=prod"
+
+        [profiles.dev]
+        selector = "farm=dev"
+    "#;
+
+    const USER: &str = r#"
+        namespace = "user"
+
+        [filter_presets]
+        mine = "artist:alice"
+
+        [profiles.prod]
+        namespace = "user-prod"
+    "#;
+
+    #[test]
+    fn later_files_then_profiles_then_flags_win() {
+        let config = Config::from_files(files(&[SITE, USER]), &cli(&[])).unwrap();
+        assert_eq!(config.profile.as_deref(), Some("prod"));
+        // The user file's profile beats its top level, which beats the site's
+        assert_eq!(config.namespace, "user-prod");
+        assert_eq!(config.selector, "farm=prod");
+        assert_eq!(config.field_manager, "site-manager");
+        assert_eq!(config.checkout_label, Config::default().checkout_label);
+
+        let config = Config::from_files(files(&[SITE, USER]), &cli(&["-n", "flag"])).unwrap();
+        assert_eq!(config.namespace, "flag");
+    }
+
+    #[test]
+    fn profile_flag_beats_the_default_profile() {
+        let config = Config::from_files(files(&[SITE, USER]), &cli(&["-p", "dev"])).unwrap();
+        assert_eq!(config.profile.as_deref(), Some("dev"));
+        assert_eq!(config.selector, "farm=dev");
+        assert_eq!(config.namespace, "user");
+
+        let error = Config::from_files(files(&[SITE]), &cli(&["-p", "qa"])).unwrap_err();
+        assert!(error.to_string().contains("unknown profile 'qa'"));
+    }
+
+    #[test]
+    fn tables_merge_key_by_key() {
+        let config = Config::from_files(files(&[SITE, USER]), &cli(&[])).unwrap();
+        let presets: Vec<&str> = config.filter_presets.keys().map(String::as_str).collect();
+        assert_eq!(presets, ["failed", "mine"]);
+
+        let override_preset = r#"
+            [filter_presets]
+            failed = "status:Error"
+        "#;
+        let config = Config::from_files(files(&[SITE, override_preset]), &cli(&[])).unwrap();
+        assert_eq!(config.filter_presets["failed"], "status:Error");
+    }
+
+    #[test]
+    fn oversized_log_patterns_are_errors() {
+        // Each compiles on its own, but not once every DCC's patterns are combined
+        let patterns = r#"
+            [log_patterns.houdini]
+            error = ['\w{150}']
+            [log_patterns.nuke]
+            error = ['\w{150}']
+        "#;
+        let error = Config::from_files(files(&[patterns]), &cli(&[])).unwrap_err();
+        assert!(error.to_string().contains("log_patterns"));
+    }
+
+    #[test]
+    fn unknown_keys_are_errors() {
+        assert!(ConfigFile::parse("namespce = \"dcc\"").is_err());
+    }
+}
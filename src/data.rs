@@ -1,54 +1,483 @@
-use crate::k8s::get_pods;
-use ::std::error::Error;
+use crate::config::Config;
+use crate::k8s::{self, COMPLETION_INDEX_ANNOTATION, RETRY_OF_LABEL};
+use crate::telemetry::{self, WatchState};
+use color_eyre::Report;
+use futures::{Stream, StreamExt};
 use k8s_openapi::{
-    api::core::v1::Pod,
+    api::{batch::v1::Job, core::v1::Pod},
     chrono::{DateTime, Utc},
 };
-use kube::{Client, ResourceExt};
+use kube::{
+    Client, ResourceExt,
+    runtime::watcher::{self, Event},
+};
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use tokio::sync::mpsc::UnboundedSender;
 
+#[derive(Default)]
 pub struct Data {
     pub name: String,
     pub controller: Option<String>,
+    /// Frame or chunk of an Indexed Job this pod renders
+    pub completion_index: Option<u32>,
+    /// Raw `status.phase` of the pod
+    pub phase: String,
+    /// kubectl style display status, derived from container states, conditions and deletion
     pub status: String,
     pub artist: String,
     pub node: String,
+    /// For label and annotation columns in the pod table
+    pub labels: BTreeMap<String, String>,
+    pub annotations: BTreeMap<String, String>,
+    /// Init containers first, in the order they run, then the others
+    pub containers: Vec<ContainerData>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// A container of a pod, for picking whose logs to show
+pub struct ContainerData {
+    pub name: String,
+    pub init: bool,
+    pub restarts: i32,
+    /// Short state, e.g. `running`, `waiting: CrashLoopBackOff` or `terminated: Error (exit 1)`
+    pub state: String,
+}
+
+/// What mf needs from a Job object
+#[derive(Clone)]
+pub struct JobData {
+    pub name: String,
+    pub uid: Option<String>,
+    /// Names this Job can be selected by, see [`k8s::job_controllers`]
+    pub controllers: Vec<String>,
+    pub artist: Option<String>,
+    /// Job this one re-runs frames of, see [`k8s::retry_frames`]
+    pub retry_of: Option<String>,
+    /// `spec.suspend`, the Job controller deletes its pods and starts no new ones
+    pub suspended: bool,
+    pub active: i32,
+    pub succeeded: i32,
+    pub failed: i32,
+    /// Whether each completion index is a frame, i.e. `completionMode: Indexed`
+    pub indexed: bool,
+    pub completions: Option<i32>,
+    /// `spec.parallelism`, how many pods may run at once
+    pub parallelism: Option<i32>,
+    pub completed_indexes: BTreeSet<u32>,
+    pub failed_indexes: BTreeSet<u32>,
+    /// Set once the job has a `Complete` or `Failed` condition
+    pub finished_state: Option<String>,
     pub started_at: Option<DateTime<Utc>>,
     pub finished_at: Option<DateTime<Utc>>,
     pub created_at: Option<DateTime<Utc>>,
 }
 
-/// Fetch data from Kubernetes pods and convert them into a sorted vector of Data structs.
-pub async fn fetch_data(client: Client) -> Result<Vec<Data>, Box<dyn Error>> {
-    let pods = get_pods(client).await?;
-    Ok(pods_to_data(pods))
+/// One row of the jobs view: a job's pods aggregated together with the Job's own status
+pub struct JobSummary {
+    pub name: String,
+    pub artist: String,
+    /// Job condition, or derived from its pods and `spec.suspend` while it runs
+    pub state: String,
+    pub total: usize,
+    pub running: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub pending: usize,
+    /// Requested `spec.parallelism`, to compare with the pods actually running
+    pub parallelism: Option<i32>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// State of one completion index of an Indexed Job, a cell in the frame grid
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FrameState {
+    Waiting,
+    Running,
+    Done,
+    Failed,
+    /// A pod for the frame failed, but the Job hasn't given up on it
+    Retrying,
+}
+
+/// Rows kept up to date by a watch
+pub trait Item {
+    fn name(&self) -> &str;
+    fn created_at(&self) -> Option<DateTime<Utc>>;
+}
+
+impl Item for Data {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.created_at
+    }
+}
+
+impl Item for JobData {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.created_at
+    }
 }
 
-/// Convert a vector of Pod objects into a sorted vector of Data structs.
-fn pods_to_data(pods: Vec<Pod>) -> Vec<Data> {
-    let mut items: Vec<Data> = pods.into_iter().map(pod_to_data).collect();
-    items.sort_by(|a, b| match (&a.created_at, &b.created_at) {
-        (Some(a), Some(b)) => b.cmp(a),
+impl Item for JobSummary {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.created_at
+    }
+}
+
+/// Incremental changes to a watched table, produced by [`watch_data`] and [`watch_jobs`].
+pub enum Update<T> {
+    /// The watch (re)started and listed every matching object, replacing the whole table.
+    Reset(Vec<T>),
+    /// An object was added or modified.
+    Applied(T),
+    /// An object was deleted, by name.
+    Deleted(String),
+    /// The watch started failing. Sent once per outage, the watcher keeps retrying.
+    Failed(String),
+}
+
+/// Watch farm pods and send table updates, only converting the pods that changed.
+/// Runs until the receiving side is dropped.
+pub async fn watch_data<M: From<Update<Data>>>(
+    client: Client,
+    config: Config,
+    tx: UnboundedSender<M>,
+) {
+    let events = k8s::watch_pods(client, &config.namespace, &config.selector);
+    let artist_label = config.artist_label.clone();
+    forward("pods", &config.namespace, events, tx, |pod| {
+        pod_to_data(pod, &artist_label)
+    })
+    .await
+}
+
+/// Watch the Jobs in the farm namespace, for the jobs view.
+/// Runs until the receiving side is dropped.
+pub async fn watch_jobs<M: From<Update<JobData>>>(
+    client: Client,
+    config: Config,
+    tx: UnboundedSender<M>,
+) {
+    let events = k8s::watch_jobs(client, &config.namespace, &config.job_selector);
+    let artist_label = config.artist_label.clone();
+    forward("jobs", &config.namespace, events, tx, |job| {
+        job_to_data(job, &artist_label)
+    })
+    .await
+}
+
+/// Turn watch events into table updates, buffering the initial listing into a single reset.
+async fn forward<K, T, M>(
+    kind: &'static str,
+    namespace: &str,
+    events: impl Stream<Item = Result<Event<K>, watcher::Error>>,
+    tx: UnboundedSender<M>,
+    convert: impl Fn(K) -> T,
+) where
+    K: ResourceExt,
+    T: Item,
+    M: From<Update<T>>,
+{
+    let mut events = std::pin::pin!(events);
+    let mut init = Vec::new();
+    let mut healthy = true;
+    while let Some(event) = events.next().await {
+        if event.is_ok() {
+            telemetry::record_watch_event(kind);
+        }
+        let update = match event {
+            Ok(Event::Init) => {
+                telemetry::set_watch_state(kind, WatchState::Listing);
+                init.clear();
+                continue;
+            }
+            Ok(Event::InitApply(object)) => {
+                init.push(convert(object));
+                continue;
+            }
+            Ok(Event::InitDone) => {
+                telemetry::set_watch_state(kind, WatchState::Watching);
+                healthy = true;
+                tracing::debug!(kind, count = init.len(), "watch synced");
+                let mut items = std::mem::take(&mut init);
+                sort_items(&mut items);
+                Update::Reset(items)
+            }
+            Ok(Event::Apply(object)) => Update::Applied(convert(object)),
+            Ok(Event::Delete(object)) => Update::Deleted(object.name_any()),
+            // The watcher backs off and restarts on its own
+            Err(e) => {
+                let report =
+                    Report::new(e).wrap_err(format!("watching {kind} in namespace {namespace}"));
+                tracing::warn!(kind, error = %format!("{report:#}"), "watch failed, retrying");
+                telemetry::record_watch_error(kind, format!("{report:#}"));
+                if !std::mem::replace(&mut healthy, false) {
+                    continue;
+                }
+                Update::Failed(k8s::describe(&report))
+            }
+        };
+        if tx.send(update.into()).is_err() {
+            break;
+        }
+    }
+}
+
+/// Apply an incremental update to a sorted table, keeping it sorted.
+pub fn apply_update<T: Item>(items: &mut Vec<T>, update: Update<T>) {
+    match update {
+        Update::Reset(new_items) => *items = new_items,
+        Update::Applied(data) => {
+            match items.iter_mut().find(|item| item.name() == data.name()) {
+                Some(item) => *item = data,
+                None => items.push(data),
+            }
+            sort_items(items);
+        }
+        Update::Deleted(name) => items.retain(|item| item.name() != name),
+        Update::Failed(_) => {}
+    }
+}
+
+/// Sort by creation time, newest first.
+fn sort_items<T: Item>(items: &mut [T]) {
+    items.sort_by(|a, b| match (a.created_at(), b.created_at()) {
+        (Some(a), Some(b)) => b.cmp(&a),
         (Some(_), None) => Ordering::Less,
         (None, Some(_)) => Ordering::Greater,
         (None, None) => Ordering::Equal,
     });
-    items
+}
+
+/// Group pods by their Job and combine them with the Jobs' status, newest job first.
+/// Jobs without any farm pods (yet) are included too, retry jobs count towards their original.
+pub fn summarize_jobs(pods: &[Data], jobs: &[JobData]) -> Vec<JobSummary> {
+    let mut by_job: BTreeMap<&str, Vec<&Data>> = BTreeMap::new();
+    for pod in pods {
+        if let Some(controller) = pod.controller.as_deref() {
+            by_job.entry(controller).or_default().push(pod);
+        }
+    }
+    let mut by_name: BTreeMap<&str, &JobData> = BTreeMap::new();
+    let mut retries: BTreeMap<&str, Vec<&JobData>> = BTreeMap::new();
+    for job in jobs {
+        match job.retry_of.as_deref() {
+            Some(original) => retries.entry(original).or_default().push(job),
+            None => {
+                by_job.entry(job.name.as_str()).or_default();
+            }
+        }
+        by_name.insert(job.name.as_str(), job);
+    }
+    let mut summaries: Vec<JobSummary> = by_job
+        .into_iter()
+        .map(|(name, pods)| {
+            let job = by_name.get(name).copied();
+            let retries = retries.get(name).map_or(&[][..], Vec::as_slice);
+            summarize_job(name, &pods, job, retries)
+        })
+        .collect();
+    sort_items(&mut summaries);
+    summaries
+}
+
+fn summarize_job(
+    name: &str,
+    pods: &[&Data],
+    job: Option<&JobData>,
+    retries: &[&JobData],
+) -> JobSummary {
+    let count = |phase: &str| pods.iter().filter(|p| p.phase == phase).count();
+    let running = count("Running");
+    let pending = count("Pending");
+    // The Job keeps counting pods that have since been cleaned up
+    let succeeded = job.map_or(count("Succeeded"), |j| {
+        j.succeeded as usize + retries.iter().map(|r| r.succeeded as usize).sum::<usize>()
+    });
+    let failed = job.map_or(count("Failed"), |j| {
+        j.failed as usize + retries.iter().map(|r| r.failed as usize).sum::<usize>()
+    });
+    let artist = job
+        .and_then(|j| j.artist.clone())
+        .or_else(|| pods.first().map(|p| p.artist.clone()))
+        .unwrap_or_else(|| "Unknown".into());
+    let state = match job.and_then(|j| j.finished_state.clone()) {
+        Some(state) => state,
+        None if job.is_some_and(|j| j.suspended) => "Suspended".into(),
+        None if running > 0 => "Running".into(),
+        None if pending > 0 => "Pending".into(),
+        None if pods.is_empty() => "Waiting".into(),
+        None => "Unknown".into(),
+    };
+    let started_at = pods
+        .iter()
+        .filter_map(|p| p.started_at)
+        .chain(job.and_then(|j| j.started_at))
+        .min();
+    let finished_at = job.and_then(|j| j.finished_at).or_else(|| {
+        // Only finished once every pod has
+        pods.iter()
+            .map(|p| p.finished_at)
+            .collect::<Option<Vec<_>>>()
+            .and_then(|times| times.into_iter().max())
+    });
+    let created_at = job
+        .and_then(|j| j.created_at)
+        .or_else(|| pods.iter().filter_map(|p| p.created_at).min());
+    JobSummary {
+        name: name.to_string(),
+        artist,
+        state,
+        total: pods.len(),
+        running,
+        succeeded,
+        failed,
+        pending,
+        parallelism: job.and_then(|j| j.parallelism),
+        started_at,
+        finished_at,
+        created_at,
+    }
+}
+
+/// State of every completion index of an Indexed Job, from the Job's completed and failed indexes
+/// and the pods still around for each index.
+pub fn frame_states(job: &JobData, pods: &[Data]) -> Vec<FrameState> {
+    let completions = job.completions.unwrap_or_default().max(0) as u32;
+    (0..completions)
+        .map(|index| {
+            if job.completed_indexes.contains(&index) {
+                return FrameState::Done;
+            }
+            // Newest first, including attempts by retry jobs
+            let attempts: Vec<&Data> = pods
+                .iter()
+                .filter(|p| {
+                    p.controller.as_deref() == Some(&job.name) && p.completion_index == Some(index)
+                })
+                .collect();
+            let any_failed = attempts.iter().any(|p| p.phase == "Failed");
+            match attempts.first().map(|p| p.phase.as_str()) {
+                // The Job status hasn't caught up yet, or a retry job rendered it
+                Some("Succeeded") => FrameState::Done,
+                Some("Running" | "Pending") if any_failed => FrameState::Retrying,
+                Some("Running") => FrameState::Running,
+                Some("Pending") => FrameState::Waiting,
+                // A finished Job won't try again
+                _ if job.failed_indexes.contains(&index) => FrameState::Failed,
+                _ if any_failed && job.finished_state.is_some() => FrameState::Failed,
+                _ if any_failed => FrameState::Retrying,
+                _ => FrameState::Waiting,
+            }
+        })
+        .collect()
+}
+
+/// Completion indexes of a job whose frames failed for good, the ones worth retrying.
+pub fn failed_frames(job: &JobData, pods: &[Data]) -> Vec<u32> {
+    frame_states(job, pods)
+        .into_iter()
+        .zip(0..)
+        .filter(|(state, _)| *state == FrameState::Failed)
+        .map(|(_, index)| index)
+        .collect()
+}
+
+/// Parse the compressed index lists the Job status uses, e.g. "1,3-5,7".
+fn parse_indexes(indexes: Option<&str>) -> BTreeSet<u32> {
+    let mut parsed = BTreeSet::new();
+    for part in indexes.unwrap_or_default().split(',') {
+        let range = match part.trim().split_once('-') {
+            Some((start, end)) => start.parse::<u32>().ok().zip(end.parse().ok()),
+            None => part.trim().parse().ok().map(|i| (i, i)),
+        };
+        if let Some((start, end)) = range {
+            parsed.extend(start..=end);
+        }
+    }
+    parsed
+}
+
+/// Convert a Job object into a JobData struct.
+fn job_to_data(job: Job, artist_label: &str) -> JobData {
+    let artist = job
+        .metadata
+        .labels
+        .as_ref()
+        .and_then(|labels| labels.get(artist_label))
+        .or_else(|| {
+            job.spec
+                .as_ref()?
+                .template
+                .metadata
+                .as_ref()?
+                .labels
+                .as_ref()?
+                .get(artist_label)
+        })
+        .cloned();
+    let spec = job.spec.as_ref();
+    let status = job.status.as_ref();
+    let finished_state = status
+        .and_then(|s| s.conditions.as_ref())
+        .and_then(|conditions| {
+            conditions
+                .iter()
+                .find(|c| (c.type_ == "Complete" || c.type_ == "Failed") && c.status == "True")
+        })
+        .map(|c| c.type_.clone());
+    JobData {
+        name: job.name_any(),
+        artist,
+        uid: job.metadata.uid.clone(),
+        controllers: k8s::job_controllers(&job),
+        retry_of: job.labels().get(RETRY_OF_LABEL).cloned(),
+        suspended: spec.and_then(|s| s.suspend).unwrap_or_default(),
+        active: status.and_then(|s| s.active).unwrap_or_default(),
+        succeeded: status.and_then(|s| s.succeeded).unwrap_or_default(),
+        failed: status.and_then(|s| s.failed).unwrap_or_default(),
+        indexed: spec.and_then(|s| s.completion_mode.as_deref()) == Some("Indexed"),
+        completions: spec.and_then(|s| s.completions),
+        parallelism: spec.and_then(|s| s.parallelism),
+        completed_indexes: parse_indexes(status.and_then(|s| s.completed_indexes.as_deref())),
+        failed_indexes: parse_indexes(status.and_then(|s| s.failed_indexes.as_deref())),
+        finished_state,
+        started_at: status.and_then(|s| s.start_time.as_ref()).map(|t| t.0),
+        finished_at: status.and_then(|s| s.completion_time.as_ref()).map(|t| t.0),
+        created_at: job.metadata.creation_timestamp.as_ref().map(|t| t.0),
+    }
 }
 
 /// Convert a single Pod object into a Data struct.
-fn pod_to_data(pod: Pod) -> Data {
-    let controller = pod.metadata.owner_references.as_ref().and_then(|owners| {
-        owners
-            .iter()
-            .find(|o| o.kind == "Job")
-            .map(|o| o.name.clone())
+fn pod_to_data(pod: Pod, artist_label: &str) -> Data {
+    // Retried frames are shown with the job they belong to
+    let controller = pod.labels().get(RETRY_OF_LABEL).cloned().or_else(|| {
+        pod.metadata.owner_references.as_ref().and_then(|owners| {
+            owners
+                .iter()
+                .find(|o| o.kind == "Job")
+                .map(|o| o.name.clone())
+        })
     });
-    let status = pod
+    let phase = pod
         .status
         .as_ref()
         .and_then(|s| s.phase.clone())
         .unwrap_or_else(|| "Unknown".into());
+    let status = pod_status(&pod, &phase);
     let node = pod
         .spec
         .as_ref()
@@ -58,7 +487,7 @@ fn pod_to_data(pod: Pod) -> Data {
         .metadata
         .labels
         .as_ref()
-        .and_then(|labels| labels.get("oom/artist"))
+        .and_then(|labels| labels.get(artist_label))
         .cloned()
         .unwrap_or_else(|| "Unknown".into());
     let started_at = pod
@@ -68,18 +497,168 @@ fn pod_to_data(pod: Pod) -> Data {
         .map(|s| s.0);
     let finished_at = pod_finished_at(&pod);
     let created_at = pod.metadata.creation_timestamp.as_ref().map(|t| t.0);
+    let completion_index = pod
+        .annotations()
+        .get(COMPLETION_INDEX_ANNOTATION)
+        .and_then(|index| index.parse().ok());
     Data {
         name: pod.name_any(),
         controller,
+        completion_index,
+        phase,
         status,
         node,
         artist,
+        labels: pod.labels().clone(),
+        annotations: pod.annotations().clone(),
+        containers: pod_containers(&pod),
         started_at,
         finished_at,
         created_at,
     }
 }
 
+/// Init and other containers of a pod with their restarts and state
+fn pod_containers(pod: &Pod) -> Vec<ContainerData> {
+    let (Some(spec), status) = (pod.spec.as_ref(), pod.status.as_ref()) else {
+        return Vec::new();
+    };
+    let init_statuses = status.and_then(|s| s.init_container_statuses.as_deref());
+    let statuses = status.and_then(|s| s.container_statuses.as_deref());
+    let init = spec
+        .init_containers
+        .iter()
+        .flatten()
+        .map(|c| (c, true, init_statuses));
+    let main = spec.containers.iter().map(|c| (c, false, statuses));
+    init.chain(main)
+        .map(|(container, init, statuses)| {
+            let status = statuses
+                .unwrap_or_default()
+                .iter()
+                .find(|s| s.name == container.name);
+            let state = status.and_then(|s| s.state.as_ref());
+            let state = match (
+                state.and_then(|s| s.running.as_ref()),
+                state.and_then(|s| s.waiting.as_ref()),
+                state.and_then(|s| s.terminated.as_ref()),
+            ) {
+                (Some(_), _, _) => "running".to_string(),
+                (_, Some(wait), _) => match wait.reason.as_deref() {
+                    Some(r) if !r.is_empty() => format!("waiting: {r}"),
+                    _ => "waiting".to_string(),
+                },
+                (_, _, Some(term)) => match term.reason.as_deref() {
+                    Some("Error") => format!("terminated: Error (exit {})", term.exit_code),
+                    Some(r) if !r.is_empty() => format!("terminated: {r}"),
+                    _ => format!("terminated: exit {}", term.exit_code),
+                },
+                _ => "not started".to_string(),
+            };
+            ContainerData {
+                name: container.name.clone(),
+                init,
+                restarts: status.map_or(0, |s| s.restart_count),
+                state,
+            }
+        })
+        .collect()
+}
+
+/// Derive the status kubectl would show for a pod, falling back to the phase.
+fn pod_status(pod: &Pod, phase: &str) -> String {
+    let Some(status) = pod.status.as_ref() else {
+        return phase.to_string();
+    };
+    let mut reason = status.reason.clone().unwrap_or_else(|| phase.to_string());
+
+    // Not placed on a node yet, e.g. no worker matches the job's requests
+    if let Some(cond) = status
+        .conditions
+        .iter()
+        .flatten()
+        .find(|c| c.type_ == "PodScheduled" && c.status == "False")
+    {
+        reason = cond
+            .reason
+            .clone()
+            .unwrap_or_else(|| "Unschedulable".into());
+    }
+
+    let init_statuses = status
+        .init_container_statuses
+        .as_deref()
+        .unwrap_or_default();
+    let mut initializing = false;
+    for (i, cs) in init_statuses.iter().enumerate() {
+        let state = cs.state.as_ref();
+        match (
+            state.and_then(|s| s.terminated.as_ref()),
+            state.and_then(|s| s.waiting.as_ref()),
+        ) {
+            (Some(term), _) if term.exit_code == 0 => continue,
+            (Some(term), _) => {
+                reason = match &term.reason {
+                    Some(r) => format!("Init:{r}"),
+                    None => match term.signal {
+                        Some(signal) if signal != 0 => format!("Init:Signal:{signal}"),
+                        _ => format!("Init:ExitCode:{}", term.exit_code),
+                    },
+                };
+            }
+            (None, Some(wait))
+                if wait
+                    .reason
+                    .as_deref()
+                    .is_some_and(|r| !r.is_empty() && r != "PodInitializing") =>
+            {
+                reason = format!("Init:{}", wait.reason.as_deref().unwrap_or_default());
+            }
+            _ => reason = format!("Init:{}/{}", i, init_statuses.len()),
+        }
+        initializing = true;
+        break;
+    }
+
+    if !initializing {
+        let mut running = false;
+        for cs in status.container_statuses.iter().flatten().rev() {
+            let Some(state) = cs.state.as_ref() else {
+                continue;
+            };
+            if let Some(wait) = state.waiting.as_ref()
+                && let Some(r) = wait.reason.as_deref().filter(|r| !r.is_empty())
+            {
+                reason = r.to_string();
+            } else if let Some(term) = state.terminated.as_ref() {
+                reason = match term.reason.as_deref() {
+                    Some("Error") => format!("Error (exit {})", term.exit_code),
+                    Some(r) if !r.is_empty() => r.to_string(),
+                    _ => match term.signal {
+                        Some(signal) if signal != 0 => format!("Signal:{signal}"),
+                        _ => format!("ExitCode:{}", term.exit_code),
+                    },
+                };
+            } else if state.running.is_some() && cs.ready {
+                running = true;
+            }
+        }
+        // A finished sidecar shouldn't hide that the main container is still going
+        if reason == "Completed" && running {
+            reason = "Running".into();
+        }
+    }
+
+    if pod.metadata.deletion_timestamp.is_some() {
+        reason = if status.reason.as_deref() == Some("NodeLost") {
+            "Unknown".into()
+        } else {
+            "Terminating".into()
+        };
+    }
+    reason
+}
+
 /// Get the latest finished_at time from the terminated container statuses of a Pod.
 fn pod_finished_at(pod: &Pod) -> Option<DateTime<Utc>> {
     pod.status
@@ -95,3 +674,161 @@ fn pod_finished_at(pod: &Pod) -> Option<DateTime<Utc>> {
         .max()
         .map(|s| s.0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn waiting(reason: &str) -> Value {
+        json!({"name": "main", "ready": false, "restartCount": 0, "image": "x", "imageID": "",
+               "state": {"waiting": {"reason": reason}}})
+    }
+
+    fn terminated(reason: Option<&str>, exit_code: i32) -> Value {
+        json!({"name": "main", "ready": false, "restartCount": 0, "image": "x", "imageID": "",
+               "state": {"terminated": {"reason": reason, "exitCode": exit_code}}})
+    }
+
+    fn running(name: &str) -> Value {
+        json!({"name": name, "ready": true, "restartCount": 0, "image": "x", "imageID": "",
+               "state": {"running": {}}})
+    }
+
+    /// Status of a pod whose `status` is `status`, with `metadata` merged in
+    fn status_of(metadata: Value, status: Value) -> String {
+        let mut pod = json!({"metadata": {"name": "p"}, "status": status});
+        if let (Some(pod), Some(extra)) = (pod["metadata"].as_object_mut(), metadata.as_object()) {
+            pod.extend(extra.clone());
+        }
+        let pod: Pod = serde_json::from_value(pod).unwrap();
+        let phase = pod
+            .status
+            .as_ref()
+            .and_then(|s| s.phase.clone())
+            .unwrap_or_default();
+        pod_status(&pod, &phase)
+    }
+
+    #[test]
+    fn statuses_follow_kubectl() {
+        let deleting = json!({"deletionTimestamp": "2026-01-01T00:00:00Z"});
+        let cases = [
+            ("phase", json!({}), json!({"phase": "Pending"}), "Pending"),
+            (
+                "running",
+                json!({}),
+                json!({"phase": "Running", "containerStatuses": [running("main")]}),
+                "Running",
+            ),
+            (
+                "image pull",
+                json!({}),
+                json!({"phase": "Pending", "containerStatuses": [waiting("ImagePullBackOff")]}),
+                "ImagePullBackOff",
+            ),
+            (
+                "creating",
+                json!({}),
+                json!({"phase": "Pending", "containerStatuses": [waiting("ContainerCreating")]}),
+                "ContainerCreating",
+            ),
+            (
+                "crash loop",
+                json!({}),
+                json!({"phase": "Running", "containerStatuses": [waiting("CrashLoopBackOff")]}),
+                "CrashLoopBackOff",
+            ),
+            (
+                "unschedulable",
+                json!({}),
+                json!({"phase": "Pending", "conditions": [
+                    {"type": "PodScheduled", "status": "False", "reason": "Unschedulable"}
+                ]}),
+                "Unschedulable",
+            ),
+            (
+                "oom",
+                json!({}),
+                json!({"phase": "Failed", "containerStatuses": [terminated(Some("OOMKilled"), 137)]}),
+                "OOMKilled",
+            ),
+            (
+                "error",
+                json!({}),
+                json!({"phase": "Failed", "containerStatuses": [terminated(Some("Error"), 3)]}),
+                "Error (exit 3)",
+            ),
+            (
+                "completed",
+                json!({}),
+                json!({"phase": "Succeeded", "containerStatuses": [terminated(Some("Completed"), 0)]}),
+                "Completed",
+            ),
+            (
+                "signal",
+                json!({}),
+                json!({"phase": "Failed", "containerStatuses": [
+                    {"name": "main", "ready": false, "restartCount": 0, "image": "x", "imageID": "",
+                     "state": {"terminated": {"exitCode": 0, "signal": 9}}}
+                ]}),
+                "Signal:9",
+            ),
+            (
+                "evicted",
+                json!({}),
+                json!({"phase": "Failed", "reason": "Evicted"}),
+                "Evicted",
+            ),
+            (
+                "init waiting",
+                json!({}),
+                json!({"phase": "Pending", "initContainerStatuses": [waiting("ImagePullBackOff")]}),
+                "Init:ImagePullBackOff",
+            ),
+            (
+                "init running",
+                json!({}),
+                json!({"phase": "Pending", "initContainerStatuses": [
+                    terminated(Some("Completed"), 0), running("fetch")
+                ]}),
+                "Init:1/2",
+            ),
+            (
+                "init failed",
+                json!({}),
+                json!({"phase": "Pending", "initContainerStatuses": [terminated(Some("Error"), 1)]}),
+                "Init:Error",
+            ),
+            (
+                "init exit code",
+                json!({}),
+                json!({"phase": "Pending", "initContainerStatuses": [terminated(None, 2)]}),
+                "Init:ExitCode:2",
+            ),
+            (
+                "sidecar done",
+                json!({}),
+                json!({"phase": "Running", "containerStatuses": [
+                    running("main"), terminated(Some("Completed"), 0)
+                ]}),
+                "Running",
+            ),
+            (
+                "terminating",
+                deleting.clone(),
+                json!({"phase": "Running", "containerStatuses": [running("main")]}),
+                "Terminating",
+            ),
+            (
+                "node lost",
+                deleting,
+                json!({"phase": "Running", "reason": "NodeLost"}),
+                "Unknown",
+            ),
+        ];
+        for (case, metadata, status, expected) in cases {
+            assert_eq!(status_of(metadata, status), expected, "{case}");
+        }
+    }
+}
@@ -1,109 +1,696 @@
+use crate::config::Config;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use color_eyre::{
+    Report, Result, Section,
+    eyre::{WrapErr, eyre},
+};
+use futures::Stream;
 use k8s_openapi::api::batch::v1::Job;
 use k8s_openapi::api::core::v1::Node;
-use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::api::core::v1::{EnvVar, Pod};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
 use kube::{
     Client, ResourceExt,
-    api::{Api, DeleteParams, ListParams, LogParams, Patch, PatchParams},
+    api::{Api, DeleteParams, LogParams, Patch, PatchParams, PostParams, Preconditions},
+    config::{InferConfigError, KubeConfigOptions, Kubeconfig, KubeconfigError},
+    runtime::{WatchStreamExt, watcher},
 };
 use serde_json::{Map, Value, json};
-use std::error::Error;
-
-const NAMESPACE: &str = "dcc";
-const FILTER_KEY: &str = "managed-by";
-const FILTER_VALUE: &str = "oom-scheduler";
-const CHECK_OUT_KEY: &str = "oom/schedulable";
-
-pub async fn get_pods(client: Client) -> Result<Vec<Pod>, Box<dyn Error>> {
-    let ns = NAMESPACE;
-    let pods: Api<Pod> = Api::namespaced(client, ns);
-    let list = pods.list(&ListParams::default()).await?;
-    let filtered: Vec<Pod> = list
-        .into_iter()
-        .filter(|list| {
-            list.metadata
-                .labels
-                .as_ref()
-                .and_then(|labels| labels.get(FILTER_KEY))
-                .map(|val| val == FILTER_VALUE)
-                .unwrap_or(false)
-        })
-        .collect();
-    Ok(filtered)
+use tracing::instrument;
+
+/// Set by mf on Jobs (and their pods) that re-run frames of another Job, naming that Job
+pub const RETRY_OF_LABEL: &str = "mf/retry-of";
+/// Completion index of a pod, set by the Job controller on pods of Indexed Jobs
+pub const COMPLETION_INDEX_ANNOTATION: &str = "batch.kubernetes.io/job-completion-index";
+/// Container `kubectl logs` and `kubectl exec` use for a pod when none is given
+pub const DEFAULT_CONTAINER_ANNOTATION: &str = "kubectl.kubernetes.io/default-container";
+/// Labels the Job controller sets on a Job's pod template, which must not be copied to a new Job
+const JOB_CONTROLLER_LABELS: [&str; 4] = [
+    "controller-uid",
+    "job-name",
+    "batch.kubernetes.io/controller-uid",
+    "batch.kubernetes.io/job-name",
+];
+
+/// A Job to act on, pinned to the object that was shown to the artist
+#[derive(Debug)]
+pub struct JobRef {
+    pub name: String,
+    pub uid: Option<String>,
+}
+
+const NO_KUBECONFIG: &str = "No usable kubeconfig was found. Pass --kubeconfig, set KUBECONFIG, \
+    or ask your pipeline TDs for the farm's kubeconfig.";
+const BAD_CONTEXT: &str =
+    "That kubeconfig context doesn't exist. List them with `kubectl config get-contexts`.";
+const UNAUTHORIZED: &str =
+    "The cluster didn't accept your credentials. Log in again to refresh them.";
+const FORBIDDEN: &str = "Your Kubernetes user isn't allowed to do this. Ask your pipeline TDs to \
+    check your RBAC permissions on the farm namespace.";
+const UNREACHABLE: &str =
+    "The Kubernetes API server can't be reached. Check your network or VPN connection.";
+
+/// Build a client from the configured kubeconfig and context, falling back to the default lookup.
+#[instrument(skip_all, err)]
+pub async fn client(config: &Config) -> Result<Client> {
+    let options = KubeConfigOptions {
+        context: config.context.clone(),
+        ..KubeConfigOptions::default()
+    };
+    let kube_config = match &config.kubeconfig {
+        Some(path) => {
+            let kubeconfig = Kubeconfig::read_from(path)
+                .wrap_err_with(|| format!("reading kubeconfig {}", path.display()))?;
+            kube::Config::from_custom_kubeconfig(kubeconfig, &options)
+                .await
+                .wrap_err("loading kubeconfig")?
+        }
+        None if config.context.is_some() => kube::Config::from_kubeconfig(&options)
+            .await
+            .wrap_err("loading kubeconfig")?,
+        None => kube::Config::infer()
+            .await
+            .wrap_err("loading cluster configuration")?,
+    };
+    Client::try_from(kube_config).wrap_err("creating Kubernetes client")
+}
+
+/// Who the kubeconfig authenticates as: who the user's OIDC id-token is for when it has one,
+/// otherwise the name of the context's user
+pub fn kubeconfig_user(config: &Config) -> Option<String> {
+    let kubeconfig = match &config.kubeconfig {
+        Some(path) => Kubeconfig::read_from(path),
+        None => Kubeconfig::read(),
+    }
+    .ok()?;
+    let context = config
+        .context
+        .as_ref()
+        .or(kubeconfig.current_context.as_ref())?;
+    let user = kubeconfig
+        .contexts
+        .iter()
+        .find(|c| &c.name == context)?
+        .context
+        .as_ref()?
+        .user
+        .clone()?;
+    let subject = kubeconfig
+        .auth_infos
+        .iter()
+        .find(|auth| auth.name == user)
+        .and_then(|auth| auth.auth_info.as_ref()?.auth_provider.as_ref())
+        .and_then(|provider| provider.config.get("id-token"))
+        .and_then(|token| token_subject(token));
+    Some(subject.unwrap_or(user))
+}
+
+/// Who a JWT is for, read without verifying it since it only picks what to show: its
+/// `preferred_username` claim, else the part of its `email` before the `@`, else its `sub`, which
+/// is often an opaque id
+fn token_subject(token: &str) -> Option<String> {
+    let payload = token.split('.').nth(1)?;
+    let payload = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+    let claims: Value = serde_json::from_slice(&payload).ok()?;
+    let claim = |name: &str| claims.get(name)?.as_str().filter(|value| !value.is_empty());
+    claim("preferred_username")
+        .or_else(|| claim("email").and_then(|email| email.split('@').next()))
+        .or_else(|| claim("sub"))
+        .map(String::from)
+}
+
+/// Plain language hint for the common ways talking to the cluster fails.
+pub fn diagnose(report: &Report) -> Option<&'static str> {
+    report.chain().find_map(|cause| {
+        if let Some(e) = cause.downcast_ref::<kube::Error>() {
+            match e {
+                kube::Error::Api(resp) if resp.code == 401 => Some(UNAUTHORIZED),
+                kube::Error::Api(resp) if resp.code == 403 => Some(FORBIDDEN),
+                kube::Error::Auth(_) => Some(UNAUTHORIZED),
+                kube::Error::Service(_) | kube::Error::HyperError(_) => Some(UNREACHABLE),
+                _ => None,
+            }
+        } else if let Some(e) = cause.downcast_ref::<KubeconfigError>() {
+            match e {
+                KubeconfigError::FindPath | KubeconfigError::ReadConfig(..) => Some(NO_KUBECONFIG),
+                KubeconfigError::LoadContext(_) => Some(BAD_CONTEXT),
+                _ => None,
+            }
+        } else if cause.is::<InferConfigError>() {
+            Some(NO_KUBECONFIG)
+        } else {
+            None
+        }
+    })
+}
+
+/// Attach the [`diagnose`] hint to a report, for printing on exit.
+pub fn with_diagnosis(report: Report) -> Report {
+    match diagnose(&report) {
+        Some(hint) => report.suggestion(hint),
+        None => report,
+    }
+}
+
+/// One line description of a report and its causes, with the [`diagnose`] hint if there is one.
+pub fn describe(report: &Report) -> String {
+    match diagnose(report) {
+        Some(hint) => format!("{report:#}. {hint}"),
+        None => format!("{report:#}"),
+    }
+}
+
+/// Watch pods matching the label selector, server side, restarting with backoff on errors.
+pub fn watch_pods(
+    client: Client,
+    namespace: &str,
+    selector: &str,
+) -> impl Stream<Item = Result<watcher::Event<Pod>, watcher::Error>> + Send + use<> {
+    let pods: Api<Pod> = Api::namespaced(client, namespace);
+    tracing::info!(namespace, selector, "watching pods");
+    watcher(pods, watcher::Config::default().labels(selector)).default_backoff()
 }
 
+/// Watch Jobs matching the label selector, or every Job when it's empty, server side, restarting
+/// with backoff on errors. Retry Jobs copy the labels of their original, so they match too.
+pub fn watch_jobs(
+    client: Client,
+    namespace: &str,
+    selector: &str,
+) -> impl Stream<Item = Result<watcher::Event<Job>, watcher::Error>> + Send + use<> {
+    let jobs: Api<Job> = Api::namespaced(client, namespace);
+    tracing::info!(namespace, selector, "watching jobs");
+    let config = match selector {
+        "" => watcher::Config::default(),
+        selector => watcher::Config::default().labels(selector),
+    };
+    watcher(jobs, config).default_backoff()
+}
+
+#[instrument(skip(client), err)]
 pub async fn stream_logs(
     client: Client,
+    namespace: &str,
     pod: &str,
-) -> Result<impl futures::AsyncBufRead + Unpin, kube::Error> {
-    let ns = NAMESPACE;
-    let pods: Api<Pod> = Api::namespaced(client, ns);
+    container: Option<&str>,
+    previous: bool,
+    tail_lines: i64,
+) -> Result<impl futures::AsyncBufRead + Unpin> {
+    let pods: Api<Pod> = Api::namespaced(client, namespace);
     let lp = LogParams {
-        follow: true,
-        tail_lines: Some(100),
+        container: container.map(str::to_string),
+        // A previous run has ended, so there's nothing to follow
+        follow: !previous,
+        previous,
+        tail_lines: Some(tail_lines),
         ..LogParams::default()
     };
-    pods.log_stream(pod, &lp).await
+    let what = match container {
+        Some(container) => format!("pod {pod} container {container}"),
+        None => format!("pod {pod}"),
+    };
+    pods.log_stream(pod, &lp)
+        .await
+        .wrap_err_with(|| format!("streaming logs for {what}"))
 }
 
 /// Check if the node is schedulable based on the label (key).
 /// If the label value is "true", the node is considered schedulable.
 /// If the label is missing or has any other value, the node is not schedulable
-pub async fn is_host_schedulable(
-    client: Client,
-    key: Option<&str>,
-) -> Result<bool, Box<dyn Error>> {
-    let key = key.unwrap_or(CHECK_OUT_KEY);
-    let node_name = hostname::get()?.to_string_lossy().into_owned();
+#[instrument(level = "debug", skip(client), err)]
+pub async fn is_host_schedulable(client: Client, key: &str) -> Result<bool> {
+    let node_name = node_name()?;
     let nodes: Api<Node> = Api::all(client);
-    let node = nodes.get(&node_name).await?;
+    let node = nodes
+        .get(&node_name)
+        .await
+        .wrap_err_with(|| format!("reading node {node_name}"))?;
     let labels = node.metadata.labels.unwrap_or_default();
-    let value = labels.get(key);
-    Ok(match value {
-        Some(v) if v == "true" => true,
-        _ => false,
-    })
+    Ok(labels.get(key).is_some_and(|v| v == "true"))
 }
 
+#[instrument(skip(client), err)]
 pub async fn set_host_schedulable(
     client: Client,
-    key: Option<&str>,
+    key: &str,
+    field_manager: &str,
     schedulable: bool,
-) -> Result<(), Box<dyn Error>> {
-    let key = key.unwrap_or(CHECK_OUT_KEY);
-    let node_name = hostname::get()?.to_string_lossy().into_owned();
+) -> Result<()> {
+    let node_name = node_name()?;
+    set_node_label(
+        client,
+        &node_name,
+        key,
+        &schedulable.to_string(),
+        field_manager,
+    )
+    .await
+}
+
+/// Set a label on a node through server side apply. Applying drops any label the manager owned but
+/// left out, so a manager should only ever apply the one label.
+#[instrument(skip(client), err)]
+async fn set_node_label(
+    client: Client,
+    node: &str,
+    key: &str,
+    value: &str,
+    field_manager: &str,
+) -> Result<()> {
     let nodes: Api<Node> = Api::all(client);
     let mut labels = Map::<String, Value>::new();
-    labels.insert(key.to_string(), Value::String(schedulable.to_string()));
+    labels.insert(key.to_string(), Value::String(value.to_string()));
     let patch = json!({
         "apiVersion": "v1",
         "kind": "Node",
         "metadata": {
-            "name": node_name,
+            "name": node,
             "labels": labels,
         }
     });
-    let parms = PatchParams::apply("flux-client-side-apply").force();
+    let parms = PatchParams::apply(field_manager).force();
     nodes
-        .patch(&node_name, &parms, &Patch::Apply(&patch))
-        .await?;
+        .patch(node, &parms, &Patch::Apply(&patch))
+        .await
+        .wrap_err_with(|| format!("patching node label {key} on {node}"))?;
+    Ok(())
+}
+
+/// An artist as it can go into a label key: characters a key can't have become `-`, and it starts
+/// and ends with a letter or digit. `None` for an unknown artist.
+pub fn label_safe_artist(artist: &str) -> Option<String> {
+    if artist == "Unknown" {
+        return None;
+    }
+    let cleaned: String = artist
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let cleaned = cleaned.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+    (!cleaned.is_empty()).then(|| cleaned.to_string())
+}
+
+/// Node label key keeping an artist's pods off a node, `avoid_node_label` with `{artist}`
+/// replaced by the artist cleaned up by [`label_safe_artist`]
+pub fn avoid_label_key(template: &str, artist: &str) -> Result<String> {
+    let safe = label_safe_artist(artist)
+        .ok_or_else(|| eyre!("artist {artist:?} can't be used in a node label"))?;
+    let key = template.replace("{artist}", &safe);
+    let name = key.rsplit('/').next().unwrap_or(&key);
+    if name.len() > 63 {
+        return Err(eyre!("node label {key} is longer than 63 characters"));
+    }
+    Ok(key)
+}
+
+/// Label a node to keep an artist's pods off it, see [`avoid_label_key`]. Each avoid label gets its
+/// own field manager, `{field_manager}-{key}`, so labelling a node for one artist doesn't drop the
+/// label of another, while the checkout label stays under `field_manager` itself.
+pub async fn avoid_node(client: Client, node: &str, key: &str, field_manager: &str) -> Result<()> {
+    set_node_label(client, node, key, "true", &format!("{field_manager}-{key}")).await
+}
+
+/// Delete a single pod, leaving its Job to create a replacement.
+#[instrument(skip(client), err)]
+pub async fn delete_pod(client: Client, namespace: &str, pod: &str) -> Result<()> {
+    let pods: Api<Pod> = Api::namespaced(client, namespace);
+    pods.delete(pod, &DeleteParams::default())
+        .await
+        .wrap_err_with(|| format!("deleting pod {pod}"))?;
+    Ok(())
+}
+
+/// Delete exactly the given Jobs and their pods, each only if it is still the object that was
+/// shown (same uid). With `dry_run` the API server validates the deletes without persisting them.
+/// Returns the outcome per Job, in order.
+pub async fn cancel_jobs(
+    client: Client,
+    namespace: &str,
+    targets: &[JobRef],
+    dry_run: bool,
+) -> Vec<(String, Result<()>)> {
+    let jobs: Api<Job> = Api::namespaced(client, namespace);
+    let mut results = Vec::new();
+    for target in targets {
+        let result = delete_job(&jobs, target, dry_run).await;
+        results.push((target.name.clone(), result));
+    }
+    results
+}
+
+#[instrument(skip(jobs), err)]
+async fn delete_job(jobs: &Api<Job>, target: &JobRef, dry_run: bool) -> Result<()> {
+    let mut params = DeleteParams::foreground();
+    params.dry_run = dry_run;
+    params.preconditions = target.uid.clone().map(|uid| Preconditions {
+        uid: Some(uid),
+        resource_version: None,
+    });
+    tracing::info!(job = %target.name, dry_run, "deleting job");
+    jobs.delete(&target.name, &params)
+        .await
+        .wrap_err_with(|| format!("deleting job {}", target.name))?;
     Ok(())
 }
 
-/// Cancel all jobs associated with the given controller id (final element provided by pdg).
-pub async fn cancel_jobs(client: Client, controller: &str) -> Result<(), Box<dyn Error>> {
-    let ns = NAMESPACE;
-    let controller_suffix = controller.rsplit('-').next();
-    let jobs: Api<Job> = Api::namespaced(client, ns);
-    let list = jobs.list(&ListParams::default()).await?;
-    for job in list.into_iter().filter(|j| {
-        j.metadata
-            .name
-            .as_deref()
-            .and_then(|n| n.rsplit('-').next())
-            == controller_suffix
-    }) {
-        jobs.delete(&job.name_any(), &DeleteParams::foreground())
-            .await?;
+/// Suspend or resume Jobs, the ones making up a controller, returning how many were patched.
+/// Suspending deletes a Job's running pods, resuming starts them from scratch.
+#[instrument(skip(client), err)]
+pub async fn suspend_jobs(
+    client: Client,
+    namespace: &str,
+    names: &[String],
+    field_manager: &str,
+    suspend: bool,
+) -> Result<usize> {
+    let jobs: Api<Job> = Api::namespaced(client, namespace);
+    let patch = json!({ "spec": { "suspend": suspend } });
+    let params = PatchParams {
+        field_manager: Some(field_manager.to_string()),
+        ..PatchParams::default()
+    };
+    for name in names {
+        tracing::info!(job = %name, suspend, "patching job");
+        jobs.patch(name, &params, &Patch::Merge(&patch))
+            .await
+            .wrap_err_with(|| format!("patching spec.suspend of job {name}"))?;
+    }
+    Ok(names.len())
+}
+
+/// Set how many pods of Jobs, the ones making up a controller, may run at once, returning how
+/// many Jobs were patched.
+#[instrument(skip(client), err)]
+pub async fn set_parallelism(
+    client: Client,
+    namespace: &str,
+    names: &[String],
+    field_manager: &str,
+    parallelism: i32,
+) -> Result<usize> {
+    let jobs: Api<Job> = Api::namespaced(client, namespace);
+    let patch = json!({ "spec": { "parallelism": parallelism } });
+    let params = PatchParams {
+        field_manager: Some(field_manager.to_string()),
+        ..PatchParams::default()
+    };
+    for name in names {
+        tracing::info!(job = %name, parallelism, "patching job");
+        jobs.patch(name, &params, &Patch::Merge(&patch))
+            .await
+            .wrap_err_with(|| format!("patching spec.parallelism of job {name}"))?;
+    }
+    Ok(names.len())
+}
+
+/// Every controller a Job belongs to: the Job itself, the Job it retries frames of and the
+/// objects owning it (e.g. a CronJob or a PDG graph object).
+pub fn job_controllers(job: &Job) -> Vec<String> {
+    let mut controllers = vec![job.name_any()];
+    controllers.extend(job.labels().get(RETRY_OF_LABEL).cloned());
+    controllers.extend(job.owner_references().iter().map(|o| o.name.clone()));
+    controllers
+}
+
+/// Re-run some frames of an Indexed Job, returning the names of the Jobs created, also when
+/// creating one of them failed and the frames after it weren't retried.
+///
+/// Indexes of a Job are always `0..completions`, so each frame becomes its own single pod Job
+/// cloned from the original. The pod gets the frame's `JOB_COMPLETION_INDEX` and completion index
+/// annotation, so the DCC renders the same frame and mf shows it in the original's frame grid.
+#[instrument(skip(client))]
+pub async fn retry_frames(
+    client: Client,
+    namespace: &str,
+    job: &str,
+    frames: &[u32],
+) -> (Vec<String>, Result<()>) {
+    let mut created = Vec::new();
+    let result = create_retry_jobs(client, namespace, job, frames, &mut created).await;
+    if let Err(e) = &result {
+        tracing::error!(error = %e, created = created.len(), "retrying frames failed");
+    }
+    (created, result)
+}
+
+async fn create_retry_jobs(
+    client: Client,
+    namespace: &str,
+    job: &str,
+    frames: &[u32],
+    created: &mut Vec<String>,
+) -> Result<()> {
+    let jobs: Api<Job> = Api::namespaced(client, namespace);
+    let original = jobs
+        .get(job)
+        .await
+        .wrap_err_with(|| format!("reading job {job}"))?;
+    for frame in frames {
+        let retry = retry_job(&original, *frame)?;
+        let retry = jobs
+            .create(&PostParams::default(), &retry)
+            .await
+            .wrap_err_with(|| format!("creating retry job for frame {frame} of {job}"))?;
+        tracing::info!(job, frame, retry = %retry.name_any(), "created retry job");
+        created.push(retry.name_any());
     }
     Ok(())
 }
+
+/// Clone a Job into a single pod Job running one of its frames again.
+fn retry_job(original: &Job, frame: u32) -> Result<Job> {
+    let name = original.name_any();
+    let mut spec = original
+        .spec
+        .clone()
+        .ok_or_else(|| eyre!("job {name} has no spec"))?;
+    // Let the API server generate a fresh selector for the new Job
+    spec.selector = None;
+    spec.manual_selector = None;
+    spec.completion_mode = Some("NonIndexed".into());
+    spec.completions = Some(1);
+    spec.parallelism = Some(1);
+    spec.suspend = None;
+    spec.backoff_limit_per_index = None;
+    spec.max_failed_indexes = None;
+    // `FailIndex` rules are only allowed alongside `backoffLimitPerIndex`
+    if let Some(policy) = spec.pod_failure_policy.as_mut() {
+        policy.rules.retain(|rule| rule.action != "FailIndex");
+        if policy.rules.is_empty() {
+            spec.pod_failure_policy = None;
+        }
+    }
+    spec.success_policy = None;
+
+    let template = spec
+        .template
+        .metadata
+        .get_or_insert_with(ObjectMeta::default);
+    let labels = template.labels.get_or_insert_with(Default::default);
+    for label in JOB_CONTROLLER_LABELS {
+        labels.remove(label);
+    }
+    labels.insert(RETRY_OF_LABEL.into(), name.clone());
+    template
+        .annotations
+        .get_or_insert_with(Default::default)
+        .insert(COMPLETION_INDEX_ANNOTATION.into(), frame.to_string());
+    if let Some(pod) = spec.template.spec.as_mut() {
+        let containers = pod
+            .containers
+            .iter_mut()
+            .chain(pod.init_containers.iter_mut().flatten());
+        for container in containers {
+            let env = container.env.get_or_insert_with(Vec::new);
+            env.retain(|var| var.name != "JOB_COMPLETION_INDEX");
+            env.push(EnvVar {
+                name: "JOB_COMPLETION_INDEX".into(),
+                value: Some(frame.to_string()),
+                ..EnvVar::default()
+            });
+        }
+    }
+
+    let mut labels = original.labels().clone();
+    for label in JOB_CONTROLLER_LABELS {
+        labels.remove(label);
+    }
+    labels.insert(RETRY_OF_LABEL.into(), name.clone());
+    Ok(Job {
+        metadata: ObjectMeta {
+            generate_name: Some(format!("{name}-f{frame}-")),
+            namespace: original.metadata.namespace.clone(),
+            labels: Some(labels),
+            annotations: Some([(COMPLETION_INDEX_ANNOTATION.into(), frame.to_string())].into()),
+            ..ObjectMeta::default()
+        },
+        spec: Some(spec),
+        status: None,
+    })
+}
+
+/// This workstation's node name, which is its hostname
+fn node_name() -> Result<String> {
+    let name = hostname::get().wrap_err("looking up hostname")?;
+    Ok(name.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Indexed Job as the farm creates it, with `pod_failure_policy` rules and `env` on its
+    /// container
+    fn indexed_job(rules: Value, env: Value) -> Job {
+        serde_json::from_value(json!({
+            "metadata": {
+                "name": "render-a",
+                "namespace": "dcc",
+                "uid": "uid-render-a",
+                "resourceVersion": "7",
+                "labels": {"oom/artist": "alice", "job-name": "render-a"},
+            },
+            "spec": {
+                "completions": 6,
+                "parallelism": 2,
+                "completionMode": "Indexed",
+                "backoffLimitPerIndex": 1,
+                "maxFailedIndexes": 3,
+                "suspend": true,
+                "manualSelector": true,
+                "selector": {"matchLabels": {"batch.kubernetes.io/controller-uid": "uid-render-a"}},
+                "podFailurePolicy": {"rules": rules},
+                "template": {
+                    "metadata": {"labels": {
+                        "oom/artist": "alice",
+                        "batch.kubernetes.io/controller-uid": "uid-render-a",
+                        "batch.kubernetes.io/job-name": "render-a",
+                        "controller-uid": "uid-render-a",
+                        "job-name": "render-a",
+                    }},
+                    "spec": {
+                        "restartPolicy": "Never",
+                        "containers": [{"name": "main", "image": "x", "env": env}],
+                    },
+                },
+            },
+        }))
+        .unwrap()
+    }
+
+    fn rule(action: &str) -> Value {
+        json!({"action": action, "onExitCodes": {"operator": "In", "values": [3]}})
+    }
+
+    #[test]
+    fn retry_runs_one_frame_as_a_fresh_single_pod_job() {
+        let original = indexed_job(json!([rule("FailIndex"), rule("Ignore")]), json!([]));
+        let retry = retry_job(&original, 4).unwrap();
+
+        assert_eq!(
+            retry.metadata.generate_name.as_deref(),
+            Some("render-a-f4-")
+        );
+        assert_eq!(retry.metadata.name, None);
+        assert_eq!(retry.metadata.namespace.as_deref(), Some("dcc"));
+        assert_eq!(retry.metadata.resource_version, None);
+        assert_eq!(
+            retry.labels().get(RETRY_OF_LABEL).map(String::as_str),
+            Some("render-a")
+        );
+        assert_eq!(
+            retry.labels().get("oom/artist").map(String::as_str),
+            Some("alice")
+        );
+        assert!(!retry.labels().contains_key("job-name"));
+        assert_eq!(
+            retry
+                .annotations()
+                .get(COMPLETION_INDEX_ANNOTATION)
+                .map(String::as_str),
+            Some("4")
+        );
+
+        let spec = retry.spec.unwrap();
+        assert_eq!(spec.selector, None);
+        assert_eq!(spec.manual_selector, None);
+        assert_eq!(spec.completion_mode.as_deref(), Some("NonIndexed"));
+        assert_eq!((spec.completions, spec.parallelism), (Some(1), Some(1)));
+        assert_eq!(spec.suspend, None);
+        assert_eq!(spec.backoff_limit_per_index, None);
+        assert_eq!(spec.max_failed_indexes, None);
+        let actions: Vec<String> = spec
+            .pod_failure_policy
+            .unwrap()
+            .rules
+            .into_iter()
+            .map(|rule| rule.action)
+            .collect();
+        assert_eq!(actions, ["Ignore"]);
+
+        let template = spec.template.metadata.unwrap();
+        let labels = template.labels.unwrap();
+        for label in JOB_CONTROLLER_LABELS {
+            assert!(!labels.contains_key(label), "{label}");
+        }
+        assert_eq!(
+            labels.get(RETRY_OF_LABEL).map(String::as_str),
+            Some("render-a")
+        );
+        assert_eq!(
+            template
+                .annotations
+                .unwrap()
+                .get(COMPLETION_INDEX_ANNOTATION)
+                .map(String::as_str),
+            Some("4")
+        );
+    }
+
+    #[test]
+    fn pod_failure_policy_of_only_fail_index_rules_is_dropped() {
+        let original = indexed_job(json!([rule("FailIndex")]), json!([]));
+        let spec = retry_job(&original, 0).unwrap().spec.unwrap();
+        assert!(spec.pod_failure_policy.is_none());
+    }
+
+    #[test]
+    fn completion_index_env_is_replaced_with_the_frame() {
+        let env = json!([
+            {"name": "JOB_COMPLETION_INDEX", "value": "0"},
+            {"name": "SHOT", "value": "sh010"},
+        ]);
+        let original = indexed_job(json!([]), env);
+        let spec = retry_job(&original, 5).unwrap().spec.unwrap();
+        let env = spec.template.spec.unwrap().containers[0]
+            .env
+            .clone()
+            .unwrap();
+        let vars: Vec<(&str, Option<&str>)> = env
+            .iter()
+            .map(|var| (var.name.as_str(), var.value.as_deref()))
+            .collect();
+        assert_eq!(
+            vars,
+            [("SHOT", Some("sh010")), ("JOB_COMPLETION_INDEX", Some("5"))]
+        );
+    }
+
+    #[test]
+    fn job_without_a_spec_is_an_error() {
+        let original = Job {
+            metadata: ObjectMeta {
+                name: Some("broken".into()),
+                ..ObjectMeta::default()
+            },
+            ..Job::default()
+        };
+        assert!(retry_job(&original, 0).is_err());
+    }
+}
@@ -1,13 +1,49 @@
-// use color_eyre::Result;
 mod app;
+mod cli;
+mod config;
 mod data;
 mod k8s;
+mod telemetry;
 use crate::app::App;
-use std::error::Error;
+use crate::cli::{Cli, Command, ConfigCommand};
+use crate::config::Config;
+use clap::Parser;
+use color_eyre::Result;
 
-fn main() -> Result<(), Box<dyn Error>> {
+#[tokio::main]
+async fn main() -> Result<()> {
+    install_hooks()?;
+    let cli = Cli::parse();
+    let config = Config::load(&cli)?;
+    if let Some(Command::Config {
+        action: ConfigCommand::Show,
+    }) = cli.command
+    {
+        print!("{}", config.show()?);
+        return Ok(());
+    }
+    let _log_guard = telemetry::init();
+    tracing::info!(profile = ?config.profile, namespace = %config.namespace, "starting mf");
+    let app = App::new(config).await.map_err(k8s::with_diagnosis)?;
     let terminal = ratatui::init();
-    let app_result = App::new()?.run(terminal);
+    let app_result = app.run(terminal).await;
     ratatui::restore();
-    app_result
+    app_result.map_err(k8s::with_diagnosis)
+}
+
+/// Install color-eyre's report handlers. Panics restore the terminal before printing, so a crash
+/// never leaves the shell stuck in raw mode on the alternate screen. Errors are only returned from
+/// `main` after the terminal has been restored, and reports are also built for errors shown
+/// inside the TUI, so the error hook must not touch the terminal.
+fn install_hooks() -> Result<()> {
+    let (panic_hook, eyre_hook) = color_eyre::config::HookBuilder::default()
+        .capture_span_trace_by_default(false)
+        .into_hooks();
+    eyre_hook.install()?;
+    let panic_hook = panic_hook.into_panic_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        ratatui::restore();
+        panic_hook(info);
+    }));
+    Ok(())
 }
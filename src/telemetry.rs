@@ -0,0 +1,174 @@
+use crate::config::xdg_dir;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tracing::{Event, Level, Subscriber, span};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{Builder, Rotation};
+use tracing_subscriber::{
+    EnvFilter, Layer, filter::Targets, fmt::format::FmtSpan, layer::Context, layer::SubscriberExt,
+    registry::LookupSpan, util::SubscriberInitExt,
+};
+
+/// Spans with this target are Kubernetes API calls, timed for the debug overlay
+const API_TARGET: &str = "mf::k8s";
+/// Rotated log files kept around
+const MAX_LOG_FILES: usize = 7;
+
+static STATS: LazyLock<Mutex<Stats>> = LazyLock::new(Mutex::default);
+
+/// Latency and error counts for one kind of API call
+#[derive(Clone, Default)]
+pub struct CallStats {
+    pub count: u64,
+    pub errors: u64,
+    pub last: Duration,
+    pub max: Duration,
+    pub total: Duration,
+}
+
+impl CallStats {
+    pub fn average(&self) -> Duration {
+        self.total
+            .checked_div(self.count as u32)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+pub enum WatchState {
+    #[default]
+    Starting,
+    Listing,
+    Watching,
+    Retrying,
+}
+
+/// State of one long running watch
+#[derive(Clone, Default)]
+pub struct WatchStats {
+    pub state: WatchState,
+    pub events: u64,
+    pub errors: u64,
+    pub last_error: Option<String>,
+}
+
+/// Everything shown in the debug overlay
+#[derive(Clone, Default)]
+pub struct Stats {
+    pub calls: BTreeMap<&'static str, CallStats>,
+    pub watches: BTreeMap<&'static str, WatchStats>,
+    pub log_dir: Option<PathBuf>,
+}
+
+fn stats() -> MutexGuard<'static, Stats> {
+    STATS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Copy of the current stats, for drawing
+pub fn snapshot() -> Stats {
+    stats().clone()
+}
+
+pub fn set_watch_state(kind: &'static str, state: WatchState) {
+    stats().watches.entry(kind).or_default().state = state;
+}
+
+pub fn record_watch_event(kind: &'static str) {
+    stats().watches.entry(kind).or_default().events += 1;
+}
+
+pub fn record_watch_error(kind: &'static str, error: String) {
+    let mut stats = stats();
+    let watch = stats.watches.entry(kind).or_default();
+    watch.state = WatchState::Retrying;
+    watch.errors += 1;
+    watch.last_error = Some(error);
+}
+
+/// Send traces to a daily rotated file under `$XDG_STATE_HOME/mf`, since the terminal belongs to
+/// the TUI. Filtered with `MF_LOG` (default `info`). Logging is skipped if the directory can't be
+/// created; the returned guard flushes the file when dropped.
+pub fn init() -> Option<WorkerGuard> {
+    let dir = xdg_dir("XDG_STATE_HOME", ".local/state")?.join("mf");
+    std::fs::create_dir_all(&dir).ok()?;
+    let appender = Builder::new()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("mf")
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(&dir)
+        .ok()?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    let filter = EnvFilter::try_from_env("MF_LOG").unwrap_or_else(|_| EnvFilter::new("info"));
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(writer)
+                .with_ansi(false)
+                .with_span_events(FmtSpan::CLOSE)
+                .with_filter(filter),
+        )
+        // Without a filter of its own the layer would enable every callsite, and spans and events
+        // of the HTTP stack would be built on every request even when `MF_LOG` leaves them out
+        .with(ApiStatsLayer.with_filter(Targets::new().with_target(API_TARGET, Level::TRACE)))
+        .try_init()
+        .ok()?;
+    stats().log_dir = Some(dir);
+    Some(guard)
+}
+
+/// Per span bookkeeping for [`ApiStatsLayer`]
+struct Timing {
+    started: Instant,
+    failed: bool,
+}
+
+/// Times Kubernetes API call spans and counts the ones that logged an error.
+struct ApiStatsLayer;
+
+impl<S> Layer<S> for ApiStatsLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if attrs.metadata().target() != API_TARGET {
+            return;
+        }
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(Timing {
+                started: Instant::now(),
+                failed: false,
+            });
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if *event.metadata().level() != Level::ERROR {
+            return;
+        }
+        if let Some(span) = ctx.event_span(event)
+            && let Some(timing) = span.extensions_mut().get_mut::<Timing>()
+        {
+            timing.failed = true;
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(timing) = span.extensions_mut().remove::<Timing>() else {
+            return;
+        };
+        let elapsed = timing.started.elapsed();
+        let mut stats = stats();
+        let call = stats.calls.entry(span.name()).or_default();
+        call.count += 1;
+        call.errors += u64::from(timing.failed);
+        call.last = elapsed;
+        call.max = call.max.max(elapsed);
+        call.total += elapsed;
+    }
+}